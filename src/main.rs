@@ -14,7 +14,10 @@
 #![warn(clippy::string_add_assign)]
 #![windows_subsystem = "windows"]
 
-use std::time::{Duration, Instant};
+use std::{
+    env, process,
+    time::{Duration, Instant},
+};
 
 use crate::{view::Ids, view_model::App};
 use conrod_core::{image::Map as ImageMap, text::Font, UiBuilder};
@@ -32,10 +35,20 @@ use glium::{
 };
 
 mod model;
+mod tool;
 mod view;
 mod view_model;
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        if let Err(e) = tool::run(&args) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     const WIDTH: u32 = 1024;
     const HEIGHT: u32 = 768;
 
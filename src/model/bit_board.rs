@@ -1,60 +1,157 @@
-use super::{Point, Size};
-use std::ops::{
-    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
-    ShrAssign,
+use super::{Point, Size, Symmetry};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+        ShrAssign, Sub,
+    },
 };
 
-#[derive(Copy, Clone, Debug)]
-pub struct BitBoard {
-    bits: u64,
+/// Unsigned integers storing one cell per bit.
+pub trait Bits:
+    Copy
+    + Debug
+    + Eq
+    + Hash
+    + BitAnd<Output = Self>
+    + BitAndAssign
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + BitXor<Output = Self>
+    + BitXorAssign
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + ShlAssign<u32>
+    + Shr<u32, Output = Self>
+    + ShrAssign<u32>
+    + Sub<Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    fn from_u128(bits: u128) -> Self;
+    fn to_u128(self) -> u128;
 }
 
-const BIT_BOARD_BITS: u32 = 64;
+macro_rules! impl_bits {
+    ($ty:ty, |$bits:ident| $from_u128:expr) => {
+        impl Bits for $ty {
+            const BITS: u32 = <$ty>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
 
-impl BitBoard {
+            fn count_ones(self) -> u32 {
+                self.count_ones()
+            }
+            fn trailing_zeros(self) -> u32 {
+                self.trailing_zeros()
+            }
+            fn leading_zeros(self) -> u32 {
+                self.leading_zeros()
+            }
+            fn from_u128($bits: u128) -> Self {
+                $from_u128
+            }
+            fn to_u128(self) -> u128 {
+                u128::from(self)
+            }
+        }
+    };
+}
+impl_bits!(u64, |bits| bits as u64);
+impl_bits!(u128, |bits| bits);
+
+/// Set of cells, stored in `u128` by default to fit every board size. Code
+/// specialised to boards of at most 64 cells may use `BitBoard<u64>`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BitBoard<B = u128> {
+    bits: B,
+}
+
+impl<B> BitBoard<B>
+where
+    B: Bits,
+{
     pub fn empty() -> Self {
-        BitBoard { bits: 0 }
+        BitBoard { bits: B::ZERO }
     }
 
     pub fn all_filled(size: Size) -> Self {
         let num_cell = size.0 * size.1;
-        if num_cell == BIT_BOARD_BITS {
-            BitBoard { bits: !0 }
+        if num_cell == B::BITS {
+            BitBoard { bits: !B::ZERO }
         } else {
             BitBoard {
-                bits: (1 << num_cell) - 1,
+                bits: !(!B::ZERO << num_cell),
             }
         }
     }
 
+    pub const fn from_bits(bits: B) -> Self {
+        BitBoard { bits }
+    }
+
     pub fn from_point(pt: Point, size: Size) -> Self {
         Self::from_offset(pt.offset(size))
     }
 
     fn from_offset(offset: u32) -> Self {
-        BitBoard { bits: 1 << offset }
+        BitBoard {
+            bits: B::ONE << offset,
+        }
+    }
+
+    pub fn bits(self) -> B {
+        self.bits
+    }
+
+    /// The same cells stored in another width, which must hold them all.
+    pub fn convert<C>(self) -> BitBoard<C>
+    where
+        C: Bits,
+    {
+        BitBoard {
+            bits: C::from_u128(self.bits.to_u128()),
+        }
     }
 
     pub fn is_empty(self) -> bool {
-        self.bits == 0
+        self.bits == B::ZERO
     }
 
     pub fn num_bits(self) -> u32 {
         self.bits.count_ones()
     }
 
-    pub fn points(self, size: Size) -> Points {
+    pub fn points(self, size: Size) -> Points<B> {
         Points { size, mask: self }
     }
 
     pub fn contains(self, pt: Point, size: Size) -> bool {
         !(self & Self::from_point(pt, size)).is_empty()
     }
+
+    /// The cells of this board of `size` moved by `sym`, on a board of
+    /// `sym.apply_size(size)`.
+    pub fn transform(self, sym: Symmetry, size: Size) -> Self {
+        if sym == Symmetry::Identity {
+            return self;
+        }
+        let new_size = sym.apply_size(size);
+        self.points(size)
+            .map(|pt| Self::from_point(sym.apply(pt, size), new_size))
+            .fold(Self::empty(), |acc, cell| acc | cell)
+    }
 }
 
 macro_rules! impl_binops {
-    ($trt:ty, $op:ident; $trt_assign:ty, $op_assign:ident) => {
-        impl $trt for BitBoard {
+    ($trt:ident, $op:ident; $trt_assign:ident, $op_assign:ident) => {
+        impl<B: Bits> $trt for BitBoard<B> {
             type Output = Self;
             fn $op(self, rhs: Self) -> Self {
                 BitBoard {
@@ -62,7 +159,7 @@ macro_rules! impl_binops {
                 }
             }
         }
-        impl $trt_assign for BitBoard {
+        impl<B: Bits> $trt_assign for BitBoard<B> {
             fn $op_assign(&mut self, rhs: Self) {
                 self.bits.$op_assign(rhs.bits)
             }
@@ -74,62 +171,62 @@ impl_binops!(BitOr, bitor; BitOrAssign, bitor_assign);
 impl_binops!(BitXor, bitxor; BitXorAssign, bitxor_assign);
 
 macro_rules! impl_shops {
-    ($trt:ty, $op:ident; $trt_assign:ty, $op_assign:ident) => {
-        impl $trt for BitBoard {
+    ($trt:ident, $op:ident; $trt_assign:ident, $op_assign:ident) => {
+        impl<B: Bits> $trt<u32> for BitBoard<B> {
             type Output = Self;
-            fn $op(self, rhs: u32) -> BitBoard {
+            fn $op(self, rhs: u32) -> Self {
                 BitBoard {
                     bits: self.bits.$op(rhs),
                 }
             }
         }
-        impl $trt_assign for BitBoard {
+        impl<B: Bits> $trt_assign<u32> for BitBoard<B> {
             fn $op_assign(&mut self, rhs: u32) {
                 self.bits.$op_assign(rhs)
             }
         }
     };
 }
-impl_shops!(Shl<u32>, shl; ShlAssign<u32>, shl_assign);
-impl_shops!(Shr<u32>, shr; ShrAssign<u32>, shr_assign);
+impl_shops!(Shl, shl; ShlAssign, shl_assign);
+impl_shops!(Shr, shr; ShrAssign, shr_assign);
 
-impl Not for BitBoard {
-    type Output = BitBoard;
-    fn not(self) -> BitBoard {
+impl<B: Bits> Not for BitBoard<B> {
+    type Output = Self;
+    fn not(self) -> Self {
         BitBoard { bits: !self.bits }
     }
 }
 
-pub struct Points {
+pub struct Points<B = u128> {
     size: Size,
-    mask: BitBoard,
+    mask: BitBoard<B>,
 }
 
-impl Iterator for Points {
+impl<B: Bits> Iterator for Points<B> {
     type Item = Point;
 
     fn next(&mut self) -> Option<Point> {
         if self.mask.is_empty() {
             return None;
         }
-        let off = self.mask.bits.trailing_zeros();
-        self.mask ^= BitBoard::from_offset(off);
-        Some(Point::from_offset(off, self.size))
+        let bits = self.mask.bits;
+        self.mask.bits = bits & (bits - B::ONE);
+        Some(Point::from_offset(bits.trailing_zeros(), self.size))
     }
 }
 
-impl DoubleEndedIterator for Points {
+impl<B: Bits> DoubleEndedIterator for Points<B> {
     fn next_back(&mut self) -> Option<Point> {
         if self.mask.is_empty() {
             return None;
         }
-        let off = self.mask.bits.leading_zeros();
+        let off = B::BITS - 1 - self.mask.bits.leading_zeros();
         self.mask ^= BitBoard::from_offset(off);
         Some(Point::from_offset(off, self.size))
     }
 }
 
-impl ExactSizeIterator for Points {
+impl<B: Bits> ExactSizeIterator for Points<B> {
     fn len(&self) -> usize {
         self.mask.bits.count_ones() as usize
     }
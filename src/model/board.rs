@@ -1,43 +1,264 @@
 use super::{
-    multi_direction::{MdMask, MdOffset},
-    BitBoard, Point, Side, Size, MAX_SIZE, MIN_SIZE,
+    bit_board::Bits,
+    multi_direction::{Directions, MdMask, MdOffset, MdWrap},
+    BitBoard, Point, Side, Size, Symmetry, MAX_SIZE, MIN_SIZE,
 };
-use std::cmp;
+use std::hash::{Hash, Hasher};
+
+const NUM_CELLS: usize = (MAX_SIZE * MAX_SIZE) as usize;
+
+// Zobrist keys of black disks, white disks and blocked cells on each cell
+// offset.
+const CELL_KEYS: [[u64; NUM_CELLS]; 3] = cell_keys();
 
 #[derive(Copy, Clone, Debug)]
 pub struct Board {
     size: Size,
     turn: Option<Side>,
-    offset: MdOffset,
     black_cells: BitBoard,
     white_cells: BitBoard,
-    move_cand: BitBoard,
+    // cells that never hold a disk
+    blocked: BitBoard,
+    // `None` from `play` until `settle`, generated on demand meanwhile
+    move_cand: Option<BitBoard>,
+    rules: Rules,
+    topology: Topology,
+    // Zobrist hash of the above, updated incrementally by `make_move`
+    hash: u64,
+}
+
+/// A move made in place by `Board::play`, which `Board::undo` takes back.
+#[derive(Copy, Clone, Debug)]
+pub struct Undo {
+    pt: Point,
+    // the placed disk and the flipped ones
+    flips: BitBoard,
+    turn: Option<Side>,
+    move_cand: Option<BitBoard>,
+    hash: u64,
+}
+
+impl Undo {
+    /// The disks the move turned over, the placed one left out.
+    pub fn flips(&self, size: Size) -> BitBoard {
+        self.flips & !BitBoard::from_point(self.pt, size)
+    }
+}
+
+// The other fields follow from these.
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        self.size == other.size
+            && self.rules == other.rules
+            && self.topology == other.topology
+            && self.turn == other.turn
+            && self.black_cells == other.black_cells
+            && self.white_cells == other.white_cells
+            && self.blocked == other.blocked
+    }
+}
+
+impl Eq for Board {}
+
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// A turn of a game: a disk placed on a cell, or a pass by a side without a
+/// move.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Move {
+    Place(Point),
+    Pass,
+}
+
+/// Arrangement of the four disks in the centre at the start of a game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StartPosition {
+    /// Each color on a diagonal, the standard start.
+    Crossed,
+    /// Each color in a column.
+    Parallel,
+}
+
+impl AsRef<str> for StartPosition {
+    fn as_ref(&self) -> &str {
+        match *self {
+            StartPosition::Crossed => "crossed",
+            StartPosition::Parallel => "parallel",
+        }
+    }
+}
+
+impl StartPosition {
+    pub fn all_values() -> [Self; 2] {
+        [StartPosition::Crossed, StartPosition::Parallel]
+    }
+
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How the final disks decide the game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rules {
+    /// The side with more disks wins.
+    Standard,
+    /// The side with fewer disks wins.
+    Misere,
+}
+
+impl AsRef<str> for Rules {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Rules::Standard => "standard",
+            Rules::Misere => "misere",
+        }
+    }
+}
+
+impl Rules {
+    pub fn all_values() -> [Self; 2] {
+        [Rules::Standard, Rules::Misere]
+    }
+
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How the edges of a board meet.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Topology {
+    /// Lines end at the edges.
+    Planar,
+    /// Lines go on from the right edge at the left one and from the bottom
+    /// at the top.
+    Torus,
+}
+
+impl AsRef<str> for Topology {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Topology::Planar => "planar",
+            Topology::Torus => "torus",
+        }
+    }
+}
+
+impl Topology {
+    pub fn all_values() -> [Self; 2] {
+        [Topology::Planar, Topology::Torus]
+    }
+
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
 }
 
 impl Board {
+    /// The standard start with black moving first.
     pub fn new(size: Size) -> Self {
+        Board::with_start(size, StartPosition::Crossed, Side::Black)
+    }
+
+    pub fn with_start(size: Size, start: StartPosition, first: Side) -> Self {
+        let (x, y) = (size.0 / 2 - 1, size.1 / 2 - 1);
+        let cells = |pts: [Point; 2]| {
+            BitBoard::from_point(pts[0], size) | BitBoard::from_point(pts[1], size)
+        };
+        let (black, white) = match start {
+            StartPosition::Crossed => (
+                cells([Point(x + 1, y), Point(x, y + 1)]),
+                cells([Point(x, y), Point(x + 1, y + 1)]),
+            ),
+            StartPosition::Parallel => (
+                cells([Point(x + 1, y), Point(x + 1, y + 1)]),
+                cells([Point(x, y), Point(x, y + 1)]),
+            ),
+        };
+        // Nobody can move on the smallest boards.
+        Board::from_cells(size, black, white, first)
+    }
+
+    /// The position with the given disks and `turn` to move, who must pass
+    /// if it has no legal move.
+    pub fn from_cells(
+        size: Size,
+        black_cells: BitBoard,
+        white_cells: BitBoard,
+        turn: Side,
+    ) -> Self {
         assert!(MIN_SIZE <= size.0 && size.0 <= MAX_SIZE);
         assert!(MIN_SIZE <= size.1 && size.1 <= MAX_SIZE);
+        assert!((black_cells & white_cells).is_empty());
 
-        let (x, y) = (size.0 / 2 - 1, size.1 / 2 - 1);
         let mut board = Board {
             size,
-            turn: Some(Side::Black),
-            offset: MdOffset::from_size(size),
-            black_cells: BitBoard::from_point(Point(x + 1, y), size)
-                | BitBoard::from_point(Point(x, y + 1), size),
-            white_cells: BitBoard::from_point(Point(x, y), size)
-                | BitBoard::from_point(Point(x + 1, y + 1), size),
-            move_cand: BitBoard::empty(),
+            turn: Some(turn),
+            black_cells,
+            white_cells,
+            blocked: BitBoard::empty(),
+            move_cand: None,
+            rules: Rules::Standard,
+            topology: Topology::Planar,
+            hash: 0,
         };
-        board.move_cand = board.compute_move_cand();
+        board.start_turn(turn);
+        board.hash = board.compute_hash();
         board
     }
 
+    /// The same position played by `rules`.
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.hash ^= rules_key(self.rules) ^ rules_key(rules);
+        self.rules = rules;
+        self
+    }
+
+    /// The same disks with `blocked` cells, which hold no disk and break the
+    /// lines of flips. The side to move must pass if the obstacles leave it
+    /// no move.
+    pub fn with_blocked(mut self, blocked: BitBoard) -> Self {
+        assert!((blocked & (self.black_cells | self.white_cells)).is_empty());
+        assert!((blocked & !BitBoard::all_filled(self.size)).is_empty());
+
+        self.hash ^= blocked_hash(self.blocked) ^ blocked_hash(blocked);
+        self.blocked = blocked;
+        if let Some(turn) = self.turn {
+            self.start_turn(turn);
+        }
+        self
+    }
+
+    /// The same disks on a board of `topology`. The side to move must pass
+    /// if the new lines leave it no move.
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.hash ^= topology_key(self.topology) ^ topology_key(topology);
+        self.topology = topology;
+        if let Some(turn) = self.turn {
+            self.start_turn(turn);
+        }
+        self
+    }
+
     pub fn size(&self) -> Size {
         self.size
     }
 
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// The side to move. After `play` and until `settle`, it may yet have
+    /// to pass.
     pub fn turn(&self) -> Option<Side> {
         self.turn
     }
@@ -50,17 +271,98 @@ impl Board {
         self.white_cells
     }
 
+    pub fn blocked_cells(&self) -> BitBoard {
+        self.blocked
+    }
+
+    /// Cells that may still take a disk.
+    pub fn empty_cells(&self) -> BitBoard {
+        BitBoard::all_filled(self.size) & !(self.black_cells | self.white_cells | self.blocked)
+    }
+
+    pub fn num_empty(&self) -> u32 {
+        self.empty_cells().num_bits()
+    }
+
+    /// The moves of the side to move, generated now if `play` left them to
+    /// `settle`.
     pub fn move_candidates(&self) -> BitBoard {
         self.move_cand
+            .unwrap_or_else(|| self.compute_move_cand(self.turn))
     }
 
-    pub fn num_disk(&self, side: Side) -> u32 {
+    pub fn cells(&self, side: Side) -> BitBoard {
         match side {
-            Side::Black => self.black_cells.num_bits(),
-            Side::White => self.white_cells.num_bits(),
+            Side::Black => self.black_cells,
+            Side::White => self.white_cells,
+        }
+    }
+
+    pub fn num_disk(&self, side: Side) -> u32 {
+        self.cells(side).num_bits()
+    }
+
+    /// Disk difference in favour of `side` as the rules count it: positive
+    /// when `side` is winning, or has won once the game is over.
+    pub fn outcome(&self, side: Side) -> i32 {
+        let diff = self.num_disk(side) as i32 - self.num_disk(side.flip()) as i32;
+        match self.rules {
+            Rules::Standard => diff,
+            Rules::Misere => -diff,
+        }
+    }
+
+    /// The side `outcome` favours, `None` on a tie.
+    pub fn winner(&self) -> Option<Side> {
+        match self.outcome(Side::Black) {
+            d if d > 0 => Some(Side::Black),
+            d if d < 0 => Some(Side::White),
+            _ => None,
         }
     }
 
+    /// Zobrist hash of the position.
+    pub fn hash_key(&self) -> u64 {
+        self.hash
+    }
+
+    /// The position rotated or reflected by `sym`, which is as good for
+    /// either side as this one.
+    pub fn transform(&self, sym: Symmetry) -> Board {
+        let size = sym.apply_size(self.size);
+        let mut board = Board {
+            size,
+            turn: self.turn,
+            black_cells: self.black_cells.transform(sym, self.size),
+            white_cells: self.white_cells.transform(sym, self.size),
+            blocked: self.blocked.transform(sym, self.size),
+            move_cand: self.move_cand.map(|cand| cand.transform(sym, self.size)),
+            rules: self.rules,
+            topology: self.topology,
+            hash: 0,
+        };
+        board.hash = board.compute_hash();
+        board
+    }
+
+    /// The representative of the positions symmetric to this one, and the
+    /// symmetry transforming this one into it.
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        Symmetry::of_size(self.size)
+            .iter()
+            .map(|&sym| (self.transform(sym), sym))
+            .min_by_key(|(board, _)| {
+                let cells = [board.black_cells, board.white_cells, board.blocked];
+                cells.map(BitBoard::bits)
+            })
+            .unwrap()
+    }
+
+    /// Hash key shared by the positions symmetric to this one.
+    pub fn canonical_hash_key(&self) -> u64 {
+        self.canonical().0.hash_key()
+    }
+
     pub fn get(&self, pt: Point) -> Option<Side> {
         assert!(pt.0 < self.size.0 && pt.1 < self.size.1);
 
@@ -73,38 +375,179 @@ impl Board {
         }
     }
 
+    /// The position after placing a disk on `pt`, the opponent passing at
+    /// once if it has no move, or `None` if the move is illegal.
     pub fn make_move(&self, pt: Point) -> Option<Board> {
-        assert!(pt.0 < self.size.0 && pt.1 < self.size.1);
+        let mut board = *self;
+        let _ = board.play(pt)?;
+        board.settle();
+        debug_assert_eq!(board.hash, board.compute_hash());
+        Some(board)
+    }
 
+    /// The position after `mv`, or `None` if it is illegal. Unlike
+    /// `make_move`, a placement leaves an opponent without a move to play
+    /// `Move::Pass`, which is legal only then.
+    pub fn apply(&self, mv: Move) -> Option<Board> {
+        let turn = self.turn?;
+        let mut board = *self;
+        match mv {
+            Move::Place(pt) => {
+                let _ = board.play(pt)?;
+                board.settle();
+                if board.turn == Some(turn) {
+                    // Takes back the pass `settle` made.
+                    board.hash ^= turn_key(board.turn) ^ turn_key(Some(turn.flip()));
+                    board.turn = Some(turn.flip());
+                    board.move_cand = Some(BitBoard::empty());
+                }
+            }
+            Move::Pass if self.must_pass() => board.set_turn(turn.flip()),
+            Move::Pass => return None,
+        }
+        debug_assert_eq!(board.hash, board.compute_hash());
+        Some(board)
+    }
+
+    /// Whether the side to move has no move while the other side has one.
+    pub fn must_pass(&self) -> bool {
+        match self.turn {
+            Some(turn) => {
+                self.move_candidates().is_empty()
+                    && !self.compute_move_cand(Some(turn.flip())).is_empty()
+            }
+            None => false,
+        }
+    }
+
+    /// Disks the side to move turns over by placing one on `pt`, the placed
+    /// one left out, or `None` if the move is illegal.
+    pub fn flips(&self, pt: Point) -> Option<BitBoard> {
+        let (_, flip) = self.flip_disks(pt)?;
+        Some(flip & !BitBoard::from_point(pt, self.size))
+    }
+
+    /// Makes the move on `pt` in place, or returns `None` if it is illegal.
+    /// The opponent gets the turn without its moves being generated, so it
+    /// may have to pass until `settle`.
+    pub fn play(&mut self, pt: Point) -> Option<Undo> {
         let (turn, flip) = self.flip_disks(pt)?;
+        let undo = Undo {
+            pt,
+            flips: flip,
+            turn: self.turn,
+            move_cand: self.move_cand,
+            hash: self.hash,
+        };
+        match turn {
+            Side::Black => {
+                self.black_cells |= flip;
+                self.white_cells &= !flip;
+            }
+            Side::White => {
+                self.white_cells |= flip;
+                self.black_cells &= !flip;
+            }
+        }
+        // The placed disk appears, the flipped ones change color.
+        let placed = BitBoard::from_point(pt, self.size);
+        self.hash ^= cells_hash(flip, turn) ^ cells_hash(flip & !placed, turn.flip());
+        self.hash ^= turn_key(self.turn) ^ turn_key(Some(turn.flip()));
+        self.turn = Some(turn.flip());
+        self.move_cand = None;
+        Some(undo)
+    }
 
-        let mut board = *self;
+    /// Takes back the move of `undo`, which must be the last one played on
+    /// this board.
+    pub fn undo(&mut self, undo: Undo) {
+        let turn = undo.turn.unwrap();
+        let placed = BitBoard::from_point(undo.pt, self.size);
         match turn {
             Side::Black => {
-                board.black_cells |= flip;
-                board.white_cells &= !flip;
+                self.black_cells &= !undo.flips;
+                self.white_cells |= undo.flips & !placed;
             }
             Side::White => {
-                board.white_cells |= flip;
-                board.black_cells &= !flip;
+                self.white_cells &= !undo.flips;
+                self.black_cells |= undo.flips & !placed;
             }
         }
+        self.turn = undo.turn;
+        self.move_cand = undo.move_cand;
+        self.hash = undo.hash;
+    }
+
+    /// Generates the moves `play` left out, making the side to move pass if
+    /// it has none, or ending the game if neither side has.
+    pub fn settle(&mut self) {
+        if self.move_cand.is_some() {
+            return;
+        }
+        match self.turn {
+            Some(turn) => self.set_turn(turn),
+            None => self.move_cand = Some(BitBoard::empty()),
+        }
+    }
 
-        for &t in &[Some(turn.flip()), Some(turn), None] {
-            board.turn = t;
-            board.move_cand = board.compute_move_cand();
-            if !board.move_cand.is_empty() {
+    // Gives the turn to `turn`, to the other side if `turn` must pass, or to
+    // nobody if the game is over.
+    fn set_turn(&mut self, turn: Side) {
+        self.hash ^= turn_key(self.turn);
+        for &t in &[Some(turn), Some(turn.flip()), None] {
+            self.turn = t;
+            let move_cand = self.compute_move_cand(t);
+            self.move_cand = Some(move_cand);
+            if !move_cand.is_empty() {
                 break;
             }
         }
+        self.hash ^= turn_key(self.turn);
+    }
 
-        Some(board)
+    // Gives the turn to `turn` even if it must pass, as the first move of a
+    // game is not made yet, or to nobody if neither side has a move.
+    fn start_turn(&mut self, turn: Side) {
+        self.set_turn(turn);
+        if self.turn == Some(turn.flip()) {
+            self.hash ^= turn_key(self.turn) ^ turn_key(Some(turn));
+            self.turn = Some(turn);
+            self.move_cand = Some(BitBoard::empty());
+        }
+    }
+
+    // How cells follow each other along the eight directions of the board.
+    pub(super) fn directions(&self) -> &'static dyn Directions<u128> {
+        match self.topology {
+            Topology::Planar => MdOffset::of_size(self.size),
+            Topology::Torus => MdWrap::of_size(self.size),
+        }
     }
 
+    fn compute_hash(&self) -> u64 {
+        let size_key = mix(u64::from(self.size.0) << 32 | u64::from(self.size.1));
+        size_key
+            ^ rules_key(self.rules)
+            ^ topology_key(self.topology)
+            ^ turn_key(self.turn)
+            ^ cells_hash(self.black_cells, Side::Black)
+            ^ cells_hash(self.white_cells, Side::White)
+            ^ blocked_hash(self.blocked)
+    }
+
+    // The placed disk and the flipped ones, without generating the moves.
+    // Points off the board are illegal moves, as they may come from files.
     fn flip_disks(&self, pt: Point) -> Option<(Side, BitBoard)> {
-        let turn = self.turn?;
+        if pt.0 >= self.size.0 || pt.1 >= self.size.1 {
+            return None;
+        }
 
-        if !self.move_cand.contains(pt, self.size) {
+        let turn = self.turn?;
+        let open = match self.move_cand {
+            Some(cand) => cand,
+            None => self.empty_cells(),
+        };
+        if !open.contains(pt, self.size) {
             return None;
         }
 
@@ -113,55 +556,257 @@ impl Board {
             Side::White => (self.white_cells, self.black_cells),
         };
 
-        let mut flip = BitBoard::from_point(pt, self.size);
-
-        let mut mask = MdMask::new(flip);
-        let mut flip_cand = MdMask::new(BitBoard::empty());
-        let cnt = cmp::max(
-            cmp::max(pt.0, MAX_SIZE - pt.0 - 1),
-            cmp::max(pt.1, MAX_SIZE - pt.1 - 1),
-        );
-        for _ in 0..cnt {
-            mask = mask.shift(self.offset);
-            for (mask, cand) in mask.iter_mut().zip(flip_cand.iter_mut()) {
-                if !(*mask & you).is_empty() {
-                    *cand |= *mask;
-                } else {
-                    if !(*mask & me).is_empty() {
-                        flip |= *cand;
-                    }
-                    *mask = BitBoard::empty();
-                }
+        // Blocked cells are neither side's, so the runs stop at them.
+        let placed = BitBoard::from_point(pt, self.size);
+        let flip = match self.topology {
+            Topology::Planar if self.size == Size(8, 8) => {
+                let (placed, me, you) = (placed.convert(), me.convert(), you.convert());
+                flip_disks::<u64, _>(placed, me, you, &MdOffset::SQUARE_8).convert()
             }
-        }
-
-        Some((turn, flip))
+            Topology::Planar => flip_disks(placed, me, you, MdOffset::of_size(self.size)),
+            Topology::Torus => flip_disks(placed, me, you, MdWrap::of_size(self.size)),
+        };
+        (flip != placed).then_some((turn, flip))
     }
 
-    fn compute_move_cand(&self) -> BitBoard {
-        let (me, you) = match self.turn {
+    fn compute_move_cand(&self, turn: Option<Side>) -> BitBoard {
+        let (me, you) = match turn {
             Some(Side::Black) => (self.black_cells, self.white_cells),
             Some(Side::White) => (self.white_cells, self.black_cells),
             None => return BitBoard::empty(),
         };
 
-        let mut cand = BitBoard::empty();
+        let blocked = self.blocked;
+        match self.topology {
+            Topology::Planar if self.size == Size(8, 8) => {
+                let (me, you, blocked) = (me.convert(), you.convert(), blocked.convert());
+                move_cand::<u64, _>(me, you, blocked, &MdOffset::SQUARE_8).convert()
+            }
+            Topology::Planar => move_cand(me, you, blocked, MdOffset::of_size(self.size)),
+            Topology::Torus => move_cand(me, you, blocked, MdWrap::of_size(self.size)),
+        }
+    }
+}
+
+// Inlined into each caller, so that the shifts and masks of the standard
+// board become constants and its cells fit in a `u64`.
+#[inline(always)]
+fn move_cand<B: Bits, D: Directions<B>>(
+    me: BitBoard<B>,
+    you: BitBoard<B>,
+    blocked: BitBoard<B>,
+    dirs: &D,
+) -> BitBoard<B> {
+    // Search: M Y Y Y E
+    let runs = MdMask::new(me).fill(you, dirs) & MdMask::new(you);
+    runs.shift(dirs).or_all() & !me & !you & !blocked
+}
+
+#[inline(always)]
+fn flip_disks<B: Bits, D: Directions<B>>(
+    placed: BitBoard<B>,
+    me: BitBoard<B>,
+    you: BitBoard<B>,
+    dirs: &D,
+) -> BitBoard<B> {
+    // Runs of opponent disks from the placed one, flipped where one of ours
+    // ends them.
+    let runs = MdMask::new(placed).fill(you, dirs);
+    let ends = runs.shift(dirs) & MdMask::new(me);
+    runs.iter()
+        .zip(ends.iter())
+        .filter(|(_, end)| !end.is_empty())
+        .fold(placed, |flip, (run, _)| flip | *run)
+}
+
+fn cells_hash(cells: BitBoard, side: Side) -> u64 {
+    keys_hash(cells, &CELL_KEYS[side as usize])
+}
+
+fn blocked_hash(cells: BitBoard) -> u64 {
+    keys_hash(cells, &CELL_KEYS[2])
+}
+
+fn keys_hash(cells: BitBoard, keys: &[u64; NUM_CELLS]) -> u64 {
+    let bits = cells.bits();
+    let mut hash = 0;
+    // By halves, as 64-bit operations are cheaper and most boards fit in one.
+    for (half, base) in [(bits as u64, 0), ((bits >> 64) as u64, 64)] {
+        let mut half = half;
+        while half != 0 {
+            hash ^= keys[base + half.trailing_zeros() as usize];
+            half &= half - 1;
+        }
+    }
+    hash
+}
+
+fn turn_key(turn: Option<Side>) -> u64 {
+    match turn {
+        Some(Side::Black) => mix(1),
+        Some(Side::White) => mix(2),
+        None => mix(3),
+    }
+}
+
+fn rules_key(rules: Rules) -> u64 {
+    match rules {
+        Rules::Standard => 0,
+        Rules::Misere => mix(4),
+    }
+}
+
+fn topology_key(topology: Topology) -> u64 {
+    match topology {
+        Topology::Planar => 0,
+        Topology::Torus => mix(5),
+    }
+}
+
+const fn cell_keys() -> [[u64; NUM_CELLS]; 3] {
+    let mut keys = [[0; NUM_CELLS]; 3];
+    let mut i = 0;
+    while i < NUM_CELLS {
+        // Offsets keep the keys apart from those of `turn_key`.
+        keys[0][i] = mix(0x100 + i as u64);
+        keys[1][i] = mix(0x200 + i as u64);
+        keys[2][i] = mix(0x300 + i as u64);
+        i += 1;
+    }
+    keys
+}
 
-        // Search: E Y Y Y M
-        let empty = MdMask::new(!me & !you);
+// splitmix64 finalizer
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
 
-        let mut you_mask = MdMask::new(you);
-        let mut me_mask = MdMask::new(me).shift(self.offset);
-        let mut you_cont_mask = empty;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::random_positions;
+    use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
 
-        for _ in 0..(MAX_SIZE - 2) {
-            you_mask = you_mask.shift(self.offset);
-            me_mask = me_mask.shift(self.offset);
-            you_cont_mask = you_cont_mask & you_mask;
+    // Flips of `turn` on `pt` found by walking each line cell by cell until
+    // it leaves the run of opponent disks, wrapping round the edges.
+    fn torus_flips(board: &Board, turn: Side, pt: Point) -> BitBoard {
+        let size = board.size();
+        if !board.empty_cells().contains(pt, size) {
+            return BitBoard::empty();
+        }
+        let (w, h) = (size.0 as i32, size.1 as i32);
+        let mut flips = BitBoard::empty();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let mut run = BitBoard::empty();
+                let (mut x, mut y) = (pt.0 as i32, pt.1 as i32);
+                loop {
+                    x = (x + dx).rem_euclid(w);
+                    y = (y + dy).rem_euclid(h);
+                    let p = Point(x as u32, y as u32);
+                    match board.get(p) {
+                        Some(side) if side == turn => {
+                            flips |= run;
+                            break;
+                        }
+                        Some(_) => run |= BitBoard::from_point(p, size),
+                        // empty, blocked or back at `pt`
+                        None => break,
+                    }
+                }
+            }
+        }
+        flips
+    }
 
-            cand |= (you_cont_mask & me_mask).or_all();
+    #[test]
+    fn torus_moves_and_flips() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let sizes = [
+            Size(4, 4),
+            Size(2, 7),
+            Size(4, 6),
+            Size(6, 4),
+            Size(5, 7),
+            Size(6, 9),
+            Size(8, 8),
+            Size(10, 6),
+            Size(9, 10),
+        ];
+        for &size in &sizes {
+            for _ in 0..200 {
+                let (mut black, mut white, mut blocked) =
+                    (BitBoard::empty(), BitBoard::empty(), BitBoard::empty());
+                for y in 0..size.1 {
+                    for x in 0..size.0 {
+                        let cell = BitBoard::from_point(Point(x, y), size);
+                        match rng.gen_range(0..20) {
+                            0..=8 => black |= cell,
+                            9..=16 => white |= cell,
+                            17 => blocked |= cell,
+                            _ => {}
+                        }
+                    }
+                }
+                let board = Board::from_cells(size, black, white, Side::Black)
+                    .with_blocked(blocked)
+                    .with_topology(Topology::Torus);
+                for &side in &Side::all_values() {
+                    let expected = (0..size.1)
+                        .flat_map(|y| (0..size.0).map(move |x| Point(x, y)))
+                        .filter(|&pt| !torus_flips(&board, side, pt).is_empty())
+                        .fold(BitBoard::empty(), |cells, pt| {
+                            cells | BitBoard::from_point(pt, size)
+                        });
+                    assert_eq!(board.compute_move_cand(Some(side)), expected);
+                }
+                if let Some(turn) = board.turn() {
+                    for pt in board.move_candidates().points(size) {
+                        assert_eq!(board.flips(pt), Some(torus_flips(&board, turn, pt)));
+                    }
+                }
+            }
         }
+    }
 
-        cand
+    #[test]
+    fn incremental_hash_keys() {
+        for &size in &[Size(4, 4), Size(5, 7), Size(8, 8), Size(10, 6)] {
+            let starts = [
+                Board::new(size),
+                Board::new(size).with_rules(Rules::Misere),
+                Board::new(size).with_topology(Topology::Torus),
+            ];
+            for start in &starts {
+                for board in random_positions(start, 1) {
+                    assert_eq!(board.hash_key(), board.compute_hash());
+                    for pt in board.move_candidates().points(size) {
+                        let child = board.make_move(pt).unwrap();
+                        assert_eq!(child.hash_key(), child.compute_hash());
+
+                        let placed = board.apply(Move::Place(pt)).unwrap();
+                        assert_eq!(placed.hash_key(), placed.compute_hash());
+                        if placed.must_pass() {
+                            let passed = placed.apply(Move::Pass).unwrap();
+                            assert_eq!(passed.hash_key(), passed.compute_hash());
+                            assert_eq!(passed.hash_key(), child.hash_key());
+                        }
+
+                        let mut in_place = board;
+                        let undo = in_place.play(pt).unwrap();
+                        assert_eq!(in_place.hash_key(), in_place.compute_hash());
+                        in_place.settle();
+                        assert_eq!(in_place.hash_key(), child.hash_key());
+                        in_place.undo(undo);
+                        assert_eq!(in_place.hash_key(), board.hash_key());
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,216 @@
+use super::{BitBoard, Board, Point, Side, Topology};
+
+// Directions come in opposite pairs, `dir` and `dir + 4`.
+const NUM_AXES: usize = 4;
+
+// Positional features for evaluators and the view.
+impl Board {
+    /// Disks that no sequence of moves can flip any more: along each of the
+    /// four lines through them, the line is full or one neighbour is beyond
+    /// the edge, blocked, or a stable disk of the same color.
+    pub fn stable_disks(&self) -> BitBoard {
+        let dirs = self.directions();
+        let all = BitBoard::all_filled(self.size());
+        let walls = self.blocked_cells();
+        let filled = self.black_cells() | self.white_cells() | walls;
+        // Cells whose neighbour along `dir` is beyond the edge or in `cells`.
+        let ends_at = |dir: usize, cells: BitBoard| {
+            let back = (dir + NUM_AXES) % 8;
+            (all & !dirs.shift(back, all)) | dirs.shift(back, cells)
+        };
+        // Cells from which every cell up to the edge or a blocked cell along
+        // a direction is filled, shrunk from the filled cells until nothing
+        // changes.
+        let full_rays = (0..2 * NUM_AXES)
+            .map(|dir| {
+                let mut ray = filled;
+                loop {
+                    let next = filled & ends_at(dir, walls | ray);
+                    if next == ray {
+                        break ray;
+                    }
+                    ray = next;
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Grown from no disks, so that only what is provably stable is.
+        let mut stable = [BitBoard::empty(); 2];
+        loop {
+            let mut grown = stable;
+            for side in Side::all_values() {
+                let anchors = stable[side.to_index()] | walls;
+                let mut cells = self.cells(side);
+                for axis in 0..NUM_AXES {
+                    let opposite = axis + NUM_AXES;
+                    cells &= (full_rays[axis] & full_rays[opposite])
+                        | ends_at(axis, anchors)
+                        | ends_at(opposite, anchors);
+                }
+                grown[side.to_index()] = cells;
+            }
+            if grown == stable {
+                return stable[0] | stable[1];
+            }
+            stable = grown;
+        }
+    }
+
+    /// Disks of `side` next to an empty cell.
+    pub fn frontier_disks(&self, side: Side) -> BitBoard {
+        self.cells(side) & self.neighbours(self.empty_cells())
+    }
+
+    /// Number of empty cells next to the disks of the opponent of `side`,
+    /// where moves may open up later.
+    pub fn potential_mobility(&self, side: Side) -> u32 {
+        (self.empty_cells() & self.neighbours(self.cells(side.flip()))).num_bits()
+    }
+
+    /// The four corner cells, none on a torus.
+    pub fn corner_cells(&self) -> BitBoard {
+        let size = self.size();
+        if self.topology() == Topology::Torus {
+            return BitBoard::empty();
+        }
+        [
+            Point(0, 0),
+            Point(size.0 - 1, 0),
+            Point(0, size.1 - 1),
+            Point(size.0 - 1, size.1 - 1),
+        ]
+        .iter()
+        .fold(BitBoard::empty(), |cells, &pt| {
+            cells | BitBoard::from_point(pt, size)
+        })
+    }
+
+    /// The cells along the edges other than the corners, none on a torus.
+    pub fn edge_cells(&self) -> BitBoard {
+        let dirs = self.directions();
+        let all = BitBoard::all_filled(self.size());
+        // Those with a neighbour missing.
+        let inner = (0..2 * NUM_AXES).fold(all, |inner, dir| inner & dirs.shift(dir, all));
+        all & !inner & !self.corner_cells()
+    }
+
+    /// Number of corners holding a disk of `side`.
+    pub fn occupied_corners(&self, side: Side) -> u32 {
+        (self.cells(side) & self.corner_cells()).num_bits()
+    }
+
+    /// Number of edge cells, corners aside, holding a disk of `side`.
+    pub fn occupied_edges(&self, side: Side) -> u32 {
+        (self.cells(side) & self.edge_cells()).num_bits()
+    }
+
+    /// The empty cells split into regions no disk or blocked cell lies
+    /// between, each connected through the eight directions. The side to
+    /// move in an odd region gets its last cell if both sides keep playing
+    /// there.
+    pub fn parity_regions(&self) -> Vec<BitBoard> {
+        let size = self.size();
+        let mut empty = self.empty_cells();
+        let mut regions = vec![];
+        while let Some(pt) = empty.points(size).next() {
+            let mut region = BitBoard::from_point(pt, size);
+            loop {
+                let grown = region | (self.neighbours(region) & empty);
+                if grown == region {
+                    break;
+                }
+                region = grown;
+            }
+            empty &= !region;
+            regions.push(region);
+        }
+        regions
+    }
+
+    // Cells next to any of `cells` in one of the eight directions.
+    fn neighbours(&self, cells: BitBoard) -> BitBoard {
+        let dirs = self.directions();
+        (0..2 * NUM_AXES).fold(BitBoard::empty(), |acc, dir| acc | dirs.shift(dir, cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::position::parse_position;
+
+    fn cells(board: &Board, pts: &[(u32, u32)]) -> BitBoard {
+        let size = board.size();
+        pts.iter().fold(BitBoard::empty(), |cells, &(x, y)| {
+            cells | BitBoard::from_point(Point(x, y), size)
+        })
+    }
+
+    #[test]
+    fn full_edge_is_stable() {
+        let board = parse_position("XXXX/-XO-/-OX-/---- O").unwrap();
+        let top = cells(&board, &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(board.stable_disks(), top);
+    }
+
+    #[test]
+    fn x_square_next_to_empty_corner_is_not_stable() {
+        let board = parse_position("----/-XO-/-OX-/---- X").unwrap();
+        assert_eq!(board.stable_disks(), BitBoard::empty());
+        // Taking the corner makes it stable along with the disk there.
+        let board = parse_position("X---/-XO-/-OX-/---- O").unwrap();
+        assert_eq!(board.stable_disks(), cells(&board, &[(0, 0)]));
+    }
+
+    #[test]
+    fn frontier_and_potential_mobility() {
+        let board = parse_position("----/-XO-/-OX-/---- X").unwrap();
+        assert_eq!(board.frontier_disks(Side::Black), board.black_cells());
+        assert_eq!(board.potential_mobility(Side::Black), 10);
+        assert_eq!(board.potential_mobility(Side::White), 10);
+        let full = parse_position("XXXX/XXOO/OOXX/OOOO X").unwrap();
+        assert_eq!(full.frontier_disks(Side::Black), BitBoard::empty());
+        assert_eq!(full.potential_mobility(Side::White), 0);
+    }
+
+    #[test]
+    fn corners_and_edges() {
+        let board = parse_position("----/-XO-/-OX-/---- X").unwrap();
+        let corners = cells(&board, &[(0, 0), (3, 0), (0, 3), (3, 3)]);
+        let edges = cells(
+            &board,
+            &[
+                (1, 0),
+                (2, 0),
+                (0, 1),
+                (3, 1),
+                (0, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+            ],
+        );
+        assert_eq!(board.corner_cells(), corners);
+        assert_eq!(board.edge_cells(), edges);
+
+        let torus = parse_position("----/-XO-/-OX-/---- X torus").unwrap();
+        assert_eq!(torus.corner_cells(), BitBoard::empty());
+        assert_eq!(torus.edge_cells(), BitBoard::empty());
+    }
+
+    #[test]
+    fn blocked_cells_split_parity_regions() {
+        let open = parse_position("-----/-XO--/-OX--/----- X").unwrap();
+        assert_eq!(open.parity_regions().len(), 1);
+
+        let board = parse_position("---#-/-XO#-/-OX#-/---#- X").unwrap();
+        let regions = board.parity_regions();
+        let sizes = regions.iter().map(|r| r.num_bits()).collect::<Vec<_>>();
+        assert_eq!(sizes, [8, 4]);
+        assert_eq!(regions[1], cells(&board, &[(4, 0), (4, 1), (4, 2), (4, 3)]));
+
+        // Around a torus the two sides meet again.
+        let torus = parse_position("---#-/-XO#-/-OX#-/---#- X torus").unwrap();
+        assert_eq!(torus.parity_regions().len(), 1);
+    }
+}
@@ -1,13 +1,27 @@
 pub use self::{
     bit_board::BitBoard,
-    board::Board,
-    player::{AiPlayer, PlayerKind},
+    board::{Board, Move, Rules, StartPosition, Topology},
+    player::{
+        analyze, annotate, random_obstacles, random_opening, solve, AiConfig, AiPlayer, Algorithm,
+        AlphaBetaPlayer, Evaluate, EvaluatorKind, EvenEvaluator, FindMove, Grade, MoveAnalysis,
+        MoveOrdering, PatternEvaluator, PatternWeights, Patterns, PlayerKind, Score, Solution,
+        Strength, StrongEvaluator, WeakEvaluator, MAX_SCORE, MIN_SCORE,
+    },
+    position::format_position,
+    puzzle::{builtin_puzzles, parse_puzzles, Puzzle},
+    record::{format_move, format_point, GameRecord},
+    symmetry::Symmetry,
 };
 
 mod bit_board;
 mod board;
+mod features;
 mod multi_direction;
 mod player;
+mod position;
+mod puzzle;
+mod record;
+mod symmetry;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Point(pub u32, pub u32);
@@ -22,19 +36,36 @@ impl Point {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Size(pub u32, pub u32);
 
 pub const MIN_SIZE: u32 = 2;
-pub const MAX_SIZE: u32 = 8;
+pub const MAX_SIZE: u32 = 10;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Side {
     Black,
     White,
 }
 
+impl AsRef<str> for Side {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Side::Black => "black",
+            Side::White => "white",
+        }
+    }
+}
+
 impl Side {
+    pub fn all_values() -> [Self; 2] {
+        [Side::Black, Side::White]
+    }
+
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+
     pub fn flip(self) -> Side {
         match self {
             Side::Black => Side::White,
@@ -1,17 +1,70 @@
-use super::{BitBoard, Point, Size};
-use std::{ops::BitAnd, slice};
+use super::{
+    bit_board::{BitBoard, Bits},
+    Point, Size, MAX_SIZE, MIN_SIZE,
+};
+use std::{cmp, ops::BitAnd, slice, sync::OnceLock};
 
+/// How cells follow each other along the eight directions of a board: right,
+/// down, down-left and down-right, then their opposites in the same order.
+pub trait Directions<B: Bits> {
+    /// The cells of `bits` one step further along `dir`, less those it takes
+    /// off the board.
+    fn shift(&self, dir: usize, bits: BitBoard<B>) -> BitBoard<B>;
+
+    /// `gen` extended along `dir` over the runs of `through` following it.
+    fn fill(&self, dir: usize, gen: BitBoard<B>, through: BitBoard<B>) -> BitBoard<B>;
+}
+
+/// Directions of a board with edges, each a single shift of the cells not
+/// on the edge it leaves by.
 #[derive(Copy, Clone, Debug)]
-pub struct MdOffset {
+pub struct MdOffset<B = u128> {
     offs: [u32; 4],
-    masks: [BitBoard; 8],
+    masks: [BitBoard<B>; 8],
+    // The longest run of disks a move can flip.
+    max_run: u32,
+}
+
+impl MdOffset<u64> {
+    /// Offsets of the standard board, for code specialised to it.
+    pub const SQUARE_8: MdOffset<u64> = MdOffset {
+        offs: [1, 8, 7, 9],
+        max_run: 6,
+        masks: [
+            BitBoard::from_bits(0x7f7f_7f7f_7f7f_7f7f),
+            BitBoard::from_bits(0x00ff_ffff_ffff_ffff),
+            BitBoard::from_bits(0x00fe_fefe_fefe_fefe),
+            BitBoard::from_bits(0x007f_7f7f_7f7f_7f7f),
+            BitBoard::from_bits(0xfefe_fefe_fefe_fefe),
+            BitBoard::from_bits(0xffff_ffff_ffff_ff00),
+            BitBoard::from_bits(0x7f7f_7f7f_7f7f_7f00),
+            BitBoard::from_bits(0xfefe_fefe_fefe_fe00),
+        ],
+    };
 }
 
 impl MdOffset {
+    /// Offsets of boards of `size`, computed once for every size.
+    pub fn of_size(size: Size) -> &'static Self {
+        static OFFSETS: OnceLock<Vec<MdOffset>> = OnceLock::new();
+        let offsets = OFFSETS.get_or_init(|| {
+            (MIN_SIZE..=MAX_SIZE)
+                .flat_map(|rows| {
+                    (MIN_SIZE..=MAX_SIZE).map(move |cols| Self::from_size(Size(cols, rows)))
+                })
+                .collect()
+        });
+        let num_cols = MAX_SIZE - MIN_SIZE + 1;
+        &offsets[((size.1 - MIN_SIZE) * num_cols + size.0 - MIN_SIZE) as usize]
+    }
+}
+
+impl<B: Bits> MdOffset<B> {
     pub fn from_size(size: Size) -> Self {
         MdOffset {
             offs: Self::offs(size),
             masks: Self::masks(size),
+            max_run: cmp::max(size.0, size.1) - 2,
         }
     }
 
@@ -26,7 +79,7 @@ impl MdOffset {
         [r_off, d_off, dl_off, dr_off]
     }
 
-    fn masks(size: Size) -> [BitBoard; 8] {
+    fn masks(size: Size) -> [BitBoard<B>; 8] {
         let all_mask = BitBoard::all_filled(size);
         let mut r_mask = all_mask;
         let mut l_mask = all_mask;
@@ -53,32 +106,207 @@ impl MdOffset {
     }
 }
 
+impl<B: Bits> Directions<B> for MdOffset<B> {
+    #[inline(always)]
+    fn shift(&self, dir: usize, bits: BitBoard<B>) -> BitBoard<B> {
+        let bits = bits & self.masks[dir];
+        if dir < 4 {
+            bits << self.offs[dir]
+        } else {
+            bits >> self.offs[dir - 4]
+        }
+    }
+
+    /// Doubles the reach at every step (Kogge-Stone occluded fill).
+    #[inline(always)]
+    fn fill(&self, dir: usize, mut gen: BitBoard<B>, through: BitBoard<B>) -> BitBoard<B> {
+        let off = self.offs[dir % 4];
+        let shift = |bits: BitBoard<B>, n: u32| {
+            if dir < 4 {
+                bits << (off * n)
+            } else {
+                bits >> (off * n)
+            }
+        };
+        // A shift lands where the opposite one may start from, so runs
+        // never wrap around an edge.
+        let mut pro = through & self.masks[(dir + 4) % 8];
+        let mut n = 1;
+        while n <= self.max_run {
+            gen |= pro & shift(gen, n);
+            pro &= shift(pro, n);
+            n *= 2;
+        }
+        gen
+    }
+}
+
+/// Directions of a board whose edges wrap around, lines going on from the
+/// right edge at the left one and from the bottom at the top.
+#[derive(Clone, Debug)]
+pub struct MdWrap<B = u128> {
+    // Per direction, the jumps of 1, 2, 4, ... steps along it as long as
+    // runs of disks may be.
+    jumps: [Vec<Jump<B>>; 8],
+    max_runs: [u32; 8],
+}
+
+// Cells moved some steps along a direction, each group of them crossing the
+// same edges by a shift of its own: left by a positive amount, right by a
+// negative one.
+type Jump<B> = Vec<(BitBoard<B>, i32)>;
+
+impl MdWrap {
+    /// Directions of wrapping boards of `size`, computed once for every
+    /// size.
+    pub fn of_size(size: Size) -> &'static Self {
+        static WRAPS: OnceLock<Vec<MdWrap>> = OnceLock::new();
+        let wraps = WRAPS.get_or_init(|| {
+            (MIN_SIZE..=MAX_SIZE)
+                .flat_map(|rows| {
+                    (MIN_SIZE..=MAX_SIZE).map(move |cols| Self::from_size(Size(cols, rows)))
+                })
+                .collect()
+        });
+        let num_cols = MAX_SIZE - MIN_SIZE + 1;
+        &wraps[((size.1 - MIN_SIZE) * num_cols + size.0 - MIN_SIZE) as usize]
+    }
+}
+
+impl<B: Bits> MdWrap<B> {
+    pub fn from_size(size: Size) -> Self {
+        const STEPS: [(i32, i32); 8] = [
+            (1, 0),
+            (0, 1),
+            (-1, 1),
+            (1, 1),
+            (-1, 0),
+            (0, -1),
+            (1, -1),
+            (-1, -1),
+        ];
+        let (w, h) = (size.0 as i32, size.1 as i32);
+        let mut jumps: [Vec<Jump<B>>; 8] = Default::default();
+        let mut max_runs = [0; 8];
+        for (dir, &(dx, dy)) in STEPS.iter().enumerate() {
+            // A line comes back to its first cell after this many steps, and
+            // a run lies between two different cells of it.
+            let cycle = match (dx, dy) {
+                (_, 0) => w,
+                (0, _) => h,
+                _ => lcm(w, h),
+            };
+            max_runs[dir] = (cycle - 2) as u32;
+            // At least the single step, which `shift` takes.
+            let mut n = 1;
+            while n == 1 || n <= cycle - 2 {
+                jumps[dir].push(Self::jump(size, dx * n, dy * n));
+                n *= 2;
+            }
+        }
+        MdWrap { jumps, max_runs }
+    }
+
+    // Every cell moved by `dx` columns and `dy` rows, grouped by the shift
+    // taking it there.
+    fn jump(size: Size, dx: i32, dy: i32) -> Jump<B> {
+        let (w, h) = (size.0 as i32, size.1 as i32);
+        let mut jump: Jump<B> = vec![];
+        for y in 0..h {
+            for x in 0..w {
+                let to = Point((x + dx).rem_euclid(w) as u32, (y + dy).rem_euclid(h) as u32);
+                let from = Point(x as u32, y as u32);
+                let shift = to.offset(size) as i32 - from.offset(size) as i32;
+                let cell = BitBoard::from_point(from, size);
+                match jump.iter_mut().find(|(_, s)| *s == shift) {
+                    Some((cells, _)) => *cells |= cell,
+                    None => jump.push((cell, shift)),
+                }
+            }
+        }
+        jump
+    }
+
+    fn apply(jump: &Jump<B>, bits: BitBoard<B>) -> BitBoard<B> {
+        jump.iter()
+            .fold(BitBoard::empty(), |moved, &(cells, shift)| {
+                if shift >= 0 {
+                    moved | (bits & cells) << shift as u32
+                } else {
+                    moved | (bits & cells) >> -shift as u32
+                }
+            })
+    }
+}
+
+impl<B: Bits> Directions<B> for MdWrap<B> {
+    fn shift(&self, dir: usize, bits: BitBoard<B>) -> BitBoard<B> {
+        Self::apply(&self.jumps[dir][0], bits)
+    }
+
+    fn fill(&self, dir: usize, mut gen: BitBoard<B>, through: BitBoard<B>) -> BitBoard<B> {
+        // Nothing falls off the board, so every cell may be reached.
+        let mut pro = through;
+        let mut n = 1;
+        for jump in &self.jumps[dir] {
+            if n > self.max_runs[dir] {
+                break;
+            }
+            gen |= pro & Self::apply(jump, gen);
+            pro &= Self::apply(jump, pro);
+            n *= 2;
+        }
+        gen
+    }
+}
+
+fn lcm(a: i32, b: i32) -> i32 {
+    let gcd = (1..=cmp::min(a, b))
+        .rev()
+        .find(|d| a % d == 0 && b % d == 0)
+        .unwrap();
+    a / gcd * b
+}
+
 #[derive(Copy, Clone, Debug)]
-pub struct MdMask {
-    masks: [BitBoard; 8],
+pub struct MdMask<B = u128> {
+    masks: [BitBoard<B>; 8],
 }
 
-impl MdMask {
-    pub fn new(mask: BitBoard) -> Self {
+impl<B: Bits> MdMask<B> {
+    #[inline(always)]
+    pub fn new(mask: BitBoard<B>) -> Self {
         MdMask { masks: [mask; 8] }
     }
 
-    pub fn shift(self, offset: MdOffset) -> Self {
+    #[inline(always)]
+    pub fn shift<D: Directions<B>>(self, dirs: &D) -> Self {
         MdMask {
             masks: [
-                (self.masks[0] & offset.masks[0]) << offset.offs[0],
-                (self.masks[1] & offset.masks[1]) << offset.offs[1],
-                (self.masks[2] & offset.masks[2]) << offset.offs[2],
-                (self.masks[3] & offset.masks[3]) << offset.offs[3],
-                (self.masks[4] & offset.masks[4]) >> offset.offs[0],
-                (self.masks[5] & offset.masks[5]) >> offset.offs[1],
-                (self.masks[6] & offset.masks[6]) >> offset.offs[2],
-                (self.masks[7] & offset.masks[7]) >> offset.offs[3],
+                dirs.shift(0, self.masks[0]),
+                dirs.shift(1, self.masks[1]),
+                dirs.shift(2, self.masks[2]),
+                dirs.shift(3, self.masks[3]),
+                dirs.shift(4, self.masks[4]),
+                dirs.shift(5, self.masks[5]),
+                dirs.shift(6, self.masks[6]),
+                dirs.shift(7, self.masks[7]),
             ],
         }
     }
 
-    pub fn or_all(self) -> BitBoard {
+    /// Extends the cells of each direction along it over the runs of
+    /// `through` following them.
+    #[inline(always)]
+    pub fn fill<D: Directions<B>>(self, through: BitBoard<B>, dirs: &D) -> Self {
+        let mut masks = self.masks;
+        for (dir, gen) in masks.iter_mut().enumerate() {
+            *gen = dirs.fill(dir, *gen, through);
+        }
+        MdMask { masks }
+    }
+
+    pub fn or_all(self) -> BitBoard<B> {
         self.masks[0]
             | self.masks[1]
             | self.masks[2]
@@ -89,13 +317,13 @@ impl MdMask {
             | self.masks[7]
     }
 
-    pub fn iter_mut(&mut self) -> slice::IterMut<'_, BitBoard> {
-        self.masks.iter_mut()
+    pub fn iter(&self) -> slice::Iter<'_, BitBoard<B>> {
+        self.masks.iter()
     }
 }
 
-impl BitAnd for MdMask {
-    type Output = MdMask;
+impl<B: Bits> BitAnd for MdMask<B> {
+    type Output = Self;
 
     fn bitand(self, rhs: Self) -> Self {
         MdMask {
@@ -1,86 +1,632 @@
-use super::{Evaluate, FindMove, Score, MAX_SCORE, MIN_SCORE};
-use crate::model::{Board, Point, Side};
-use std::{cmp, u32};
+use super::{
+    transposition::{Bound, Entry, TranspositionTable},
+    Budget, Evaluate, FindMove, Score, MAX_SCORE, MIN_SCORE,
+};
+use crate::model::{Board, Point, Side, Size};
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+// Nodes with at least this budget order their children by a shallow search.
+const SHALLOW_ORDERING_MIN_EVAL: f64 = 10_000.0;
+const SHALLOW_ORDERING_EVAL: f64 = 64.0;
+// The same for depth limited searches.
+const SHALLOW_ORDERING_MIN_DEPTH: f64 = 4.0;
+const SHALLOW_ORDERING_DEPTH: f64 = 1.0;
+
+// First budget of the growing searches which fill a time budget.
+const TIMED_SEARCH_START_EVAL: f64 = 1_000.0;
+
+// Half width of the window around the previous search score.
+const ASPIRATION_WIDTH: f64 = 0.5;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MoveOrdering {
+    Natural,
+    Heuristic,
+}
 
 #[derive(Clone, Debug)]
 pub struct Player<E> {
     side: Side,
-    num_eval: u32,
+    budget: Budget,
     evaluator: E,
+    ordering: MoveOrdering,
+    num_threads: usize,
+    abort: Arc<AtomicBool>,
+    table: Arc<TranspositionTable>,
+    last_score: Option<Score>,
+    num_nodes: u64,
 }
 
 impl<E> Player<E> {
     pub fn new(side: Side, num_eval: u32, evaluator: E) -> Self {
+        Self::with_ordering(side, num_eval, evaluator, MoveOrdering::Heuristic)
+    }
+
+    pub fn with_ordering(side: Side, num_eval: u32, evaluator: E, ordering: MoveOrdering) -> Self {
         Player {
             side,
-            num_eval,
+            budget: Budget::Nodes(num_eval),
             evaluator,
+            ordering,
+            num_threads: 1,
+            abort: Arc::new(AtomicBool::new(false)),
+            table: Arc::new(TranspositionTable::new()),
+            last_score: None,
+            num_nodes: 0,
         }
     }
+
+    /// Limits searches by `budget` instead of the number of evaluations
+    /// given on construction.
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Searches with `num_threads` threads sharing one transposition table
+    /// (Lazy SMP).
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads.max(1);
+        self
+    }
+
+    /// Makes running and later searches return as soon as `abort` is set.
+    pub fn abort_flag(mut self, abort: Arc<AtomicBool>) -> Self {
+        self.abort = abort;
+        self
+    }
+
+    /// Number of nodes visited by the last search, summed over all threads.
+    pub fn num_nodes(&self) -> u64 {
+        self.num_nodes
+    }
 }
 
-impl<E> FindMove for Player<E>
+impl<E> Player<E>
 where
-    E: Evaluate,
+    E: Evaluate + Sync,
 {
-    fn find_move(&mut self, board: Board) -> Point {
+    /// Searches `board` and returns the best move with its score for this player.
+    ///
+    /// The result is meaningless if the search was aborted.
+    pub fn search(&mut self, board: &Board) -> (Point, Score) {
+        self.search_until(board, None)
+    }
+
+    /// Scores every move of `board` with a full window search, splitting
+    /// the budget among them.
+    pub fn score_moves(&mut self, board: &Board) -> Vec<(Point, Score)> {
         assert_eq!(board.turn(), Some(self.side));
+        self.within_budget(|player, limit| player.run_all(board, limit))
+    }
 
-        let cands = board.move_candidates();
-        let size = board.size();
-        let num_cands = cands.num_bits();
-        let child_num_eval = f64::from(self.num_eval) / f64::from(num_cands);
+    // Like `search`, but also gives up once `stop` is set.
+    fn search_until(&mut self, board: &Board, stop: Option<&AtomicBool>) -> (Point, Score) {
+        assert_eq!(board.turn(), Some(self.side));
+        self.within_budget(|player, limit| player.run(board, limit, stop))
+    }
+
+    fn within_budget<T, F>(&mut self, mut run: F) -> T
+    where
+        F: FnMut(&mut Self, Limit) -> (T, Status),
+    {
+        let limit = |budget, depth_limited| Limit {
+            budget,
+            depth_limited,
+            deadline: None,
+        };
+        match self.budget {
+            Budget::Nodes(num_eval) => run(self, limit(f64::from(num_eval), false)).0,
+            Budget::Depth(depth) => run(self, limit(f64::from(depth), true)).0,
+            Budget::Time(time) => {
+                // Searches with growing budgets until the time runs out, and
+                // takes the result of the last search which completed.
+                let deadline = Instant::now() + time;
+                let mut limit = Limit {
+                    deadline: Some(deadline),
+                    ..limit(TIMED_SEARCH_START_EVAL, false)
+                };
+                let mut best = None;
+                loop {
+                    let (result, status) = run(self, limit);
+                    match status {
+                        Status::Stopped => return best.unwrap_or(result),
+                        // A larger budget cannot change anything once the
+                        // whole game tree fits in it.
+                        Status::Exact => return result,
+                        Status::Completed => best = Some(result),
+                    }
+                    limit.budget *= 4.0;
+                }
+            }
+        }
+    }
 
-        cands
+    fn run_all(&mut self, board: &Board, limit: Limit) -> (Vec<(Point, Score)>, Status) {
+        let mut search = self.new_search(board, limit, 0, None, None);
+        let size = board.size();
+        let num_moves = board.move_candidates().num_bits() as usize;
+        let child_num_eval = search.child_budget(limit.budget, num_moves);
+        let mut board = *board;
+        let scores = board
+            .move_candidates()
             .points(size)
-            .map(move |pt| (pt, board.make_move(pt).unwrap()))
-            .map(|(pt, board)| (pt, self.get_score(&board, child_num_eval)))
-            .max_by_key(|e| e.1)
-            .unwrap()
-            .0
+            .map(|pt| {
+                let undo = board.play(pt).unwrap();
+                let score = search.child_score(
+                    &mut board,
+                    self.side,
+                    child_num_eval,
+                    MIN_SCORE,
+                    MAX_SCORE,
+                    1,
+                );
+                board.undo(undo);
+                (pt, score)
+            })
+            .collect();
+        let status = search.status();
+        self.num_nodes = search.num_nodes;
+        (scores, status)
+    }
+
+    fn run(
+        &mut self,
+        board: &Board,
+        limit: Limit,
+        stop: Option<&AtomicBool>,
+    ) -> ((Point, Score), Status) {
+        let helpers_done = AtomicBool::new(false);
+        let (result, status, num_nodes) = thread::scope(|scope| {
+            // Helpers search the same tree in a different order, filling the
+            // shared table with results the main search can reuse.
+            let helpers = (1..self.num_threads)
+                .map(|helper| {
+                    let mut search =
+                        self.new_search(board, limit, helper, stop, Some(&helpers_done));
+                    scope.spawn(move || {
+                        let _ = search.root(board, limit.budget, MIN_SCORE, MAX_SCORE);
+                        search.num_nodes
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut search = self.new_search(board, limit, 0, stop, None);
+            let result = match self.last_score {
+                Some(Score::Running(guess)) => search.aspiration(board, limit.budget, guess),
+                _ => search.root(board, limit.budget, MIN_SCORE, MAX_SCORE),
+            };
+            let status = search.status();
+
+            helpers_done.store(true, Ordering::Relaxed);
+            let num_nodes = helpers
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum::<u64>()
+                + search.num_nodes;
+            (result, status, num_nodes)
+        });
+
+        self.num_nodes = num_nodes;
+        if status != Status::Stopped {
+            self.last_score = Some(result.1);
+        }
+        (result, status)
+    }
+
+    fn new_search<'a>(
+        &'a self,
+        board: &Board,
+        limit: Limit,
+        helper: usize,
+        stop: Option<&'a AtomicBool>,
+        helpers_done: Option<&'a AtomicBool>,
+    ) -> Search<'a, E> {
+        Search {
+            side: self.side,
+            evaluator: &self.evaluator,
+            ordering: self.ordering,
+            table: &self.table,
+            limit,
+            helper,
+            abort: &self.abort,
+            stop,
+            helpers_done,
+            killers: vec![],
+            history: vec![0; (board.size().0 * board.size().1) as usize],
+            reached_budget: false,
+            num_nodes: 0,
+        }
     }
 }
 
-impl<E> Player<E>
+impl<E> FindMove for Player<E>
+where
+    E: Evaluate + Sync,
+{
+    fn find_move(&mut self, board: Board) -> Point {
+        self.search(&board).0
+    }
+
+    fn score_moves(&mut self, board: &Board) -> Option<Vec<(Point, Score)>> {
+        Some(Player::score_moves(self, board))
+    }
+
+    fn expected_reply(&self, board: &Board) -> Option<Point> {
+        self.table.best_move(board)
+    }
+
+    fn ponder(&mut self, board: Board, stop: &AtomicBool) -> Option<Point> {
+        let (pt, _) = self.search_until(&board, Some(stop));
+        if stop.load(Ordering::Relaxed) {
+            None
+        } else {
+            Some(pt)
+        }
+    }
+}
+
+// The budget is split evenly among the children of a node, or decreases by
+// one per ply if depth limited.
+#[derive(Copy, Clone, Debug)]
+struct Limit {
+    budget: f64,
+    depth_limited: bool,
+    deadline: Option<Instant>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Status {
+    Stopped,
+    Completed,
+    // completed without reaching the budget anywhere
+    Exact,
+}
+
+struct Search<'a, E> {
+    side: Side,
+    evaluator: &'a E,
+    ordering: MoveOrdering,
+    table: &'a TranspositionTable,
+    limit: Limit,
+    helper: usize,
+    abort: &'a AtomicBool,
+    stop: Option<&'a AtomicBool>,
+    helpers_done: Option<&'a AtomicBool>,
+    killers: Vec<[Option<Point>; 2]>,
+    history: Vec<u64>,
+    reached_budget: bool,
+    num_nodes: u64,
+}
+
+impl<'a, E> Search<'a, E>
 where
     E: Evaluate,
 {
-    fn get_score(&self, board: &Board, num_eval: f64) -> Score {
-        self.alphabeta(board, num_eval, MIN_SCORE, MAX_SCORE)
+    fn aspiration(&mut self, board: &Board, num_eval: f64, guess: f64) -> (Point, Score) {
+        let mut alpha = Score::Running(guess - ASPIRATION_WIDTH);
+        let mut beta = Score::Running(guess + ASPIRATION_WIDTH);
+        loop {
+            let (pt, score) = self.root(board, num_eval, alpha, beta);
+            if self.is_stopped() {
+                return (pt, score);
+            }
+            if score <= alpha {
+                alpha = MIN_SCORE;
+            } else if score >= beta {
+                beta = MAX_SCORE;
+            } else {
+                return (pt, score);
+            }
+        }
+    }
+
+    fn root(&mut self, board: &Board, num_eval: f64, alpha: Score, beta: Score) -> (Point, Score) {
+        let mut board = *board;
+        let (score, pt) = self.negamax(&mut board, self.side, num_eval, alpha, beta, 0);
+        // Only an aborted search can end without a move.
+        let pt = pt.unwrap_or_else(|| board.move_candidates().points(board.size()).next().unwrap());
+        (pt, score)
+    }
+
+    fn is_stopped(&self) -> bool {
+        let is_set = |flag: Option<&AtomicBool>| flag.is_some_and(|f| f.load(Ordering::Relaxed));
+        self.abort.load(Ordering::Relaxed)
+            || is_set(self.stop)
+            || is_set(self.helpers_done)
+            || self
+                .limit
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    fn status(&self) -> Status {
+        if self.is_stopped() {
+            Status::Stopped
+        } else if self.reached_budget {
+            Status::Completed
+        } else {
+            Status::Exact
+        }
     }
 
-    fn alphabeta(&self, board: &Board, num_eval: f64, alpha: Score, beta: Score) -> Score {
-        if num_eval <= 1.0 || board.turn().is_none() {
-            return self.evaluator.evaluate(board, self.side);
+    fn is_leaf(&self, num_eval: f64) -> bool {
+        if self.limit.depth_limited {
+            num_eval < 1.0
+        } else {
+            num_eval <= 1.0
         }
+    }
 
-        let cands = board.move_candidates();
-        let size = board.size();
-        let num_cands = cands.num_bits();
-        let child_num_eval = num_eval / f64::from(num_cands);
-
-        let it = cands.points(size).map(|pt| board.make_move(pt).unwrap());
-
-        if board.turn() == Some(self.side) {
-            let mut alpha = alpha;
-            for board in it {
-                let score = self.alphabeta(&board, child_num_eval, alpha, beta);
-                alpha = cmp::max(alpha, score);
-                if alpha >= beta {
-                    return beta;
+    fn child_budget(&self, num_eval: f64, num_children: usize) -> f64 {
+        if self.limit.depth_limited {
+            num_eval - 1.0
+        } else {
+            num_eval / num_children as f64
+        }
+    }
+
+    // Fail-soft principal variation search, making the moves in place on
+    // `board`. Scores are seen from `side`, the side to move on `board` (or
+    // the side which made the last move when the game has ended).
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        side: Side,
+        num_eval: f64,
+        alpha: Score,
+        beta: Score,
+        ply: usize,
+    ) -> (Score, Option<Point>) {
+        self.num_nodes += 1;
+
+        if board.turn().is_none() {
+            return (self.evaluate(board, side), None);
+        }
+        if self.is_leaf(num_eval) && ply > 0 {
+            self.reached_budget = true;
+            return (self.evaluate(board, side), None);
+        }
+        if self.is_stopped() {
+            return (MIN_SCORE, None);
+        }
+
+        // Only entries searched with exactly the same budget are used, so that
+        // the result is the same as without the table. Node budgets are split
+        // among the children, so transpositions seldom meet with the same one
+        // and the table mostly just orders the moves; depth budgets do meet.
+        if ply > 0 {
+            if let Some(entry) = self.table.probe(board) {
+                let same_budget = entry.num_eval == num_eval;
+                let usable = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if same_budget && usable {
+                    return (entry.score, entry.best_move);
                 }
             }
-            alpha
-        } else {
-            let mut beta = beta;
-            for board in it {
-                let score = self.alphabeta(&board, child_num_eval, alpha, beta);
-                beta = cmp::min(beta, score);
-                if alpha >= beta {
-                    return alpha;
+        }
+
+        let children = self.ordered_children(board, side, num_eval, ply);
+        let child_num_eval = self.child_budget(num_eval, children.len());
+
+        let orig_alpha = alpha;
+        let mut alpha = alpha;
+        let mut best_score = MIN_SCORE;
+        let mut best = None;
+        for (i, pt) in children.into_iter().enumerate() {
+            let undo = board.play(pt).unwrap();
+            let score = if i == 0 {
+                self.child_score(board, side, child_num_eval, alpha, beta, ply + 1)
+            } else {
+                // Null window test: no score lies strictly between `alpha` and
+                // `alpha.next_up()`, so the result tells which side of `alpha`
+                // the child's score is on.
+                let null_beta = alpha.next_up();
+                let score =
+                    self.child_score(board, side, child_num_eval, alpha, null_beta, ply + 1);
+                if score > alpha && score < beta {
+                    self.child_score(board, side, child_num_eval, score, beta, ply + 1)
+                } else {
+                    score
                 }
+            };
+            board.undo(undo);
+            if self.is_stopped() {
+                return (best_score, best);
+            }
+
+            if best.is_none() || score > best_score {
+                best_score = score;
+                best = Some(pt);
             }
-            beta
+            if best_score >= beta {
+                self.record_cutoff(board, pt, num_eval, ply);
+                break;
+            }
+            if best_score > alpha {
+                alpha = best_score;
+            }
+        }
+
+        let bound = if best_score <= orig_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(
+            board,
+            Entry {
+                best_move: best,
+                score: best_score,
+                bound,
+                num_eval,
+            },
+        );
+        (best_score, best)
+    }
+
+    // `child` is the position after a move by `side`, made in place.
+    fn child_score(
+        &mut self,
+        child: &mut Board,
+        side: Side,
+        num_eval: f64,
+        alpha: Score,
+        beta: Score,
+        ply: usize,
+    ) -> Score {
+        child.settle();
+        if child.turn() == Some(side.flip()) {
+            -self
+                .negamax(child, side.flip(), num_eval, -beta, -alpha, ply)
+                .0
+        } else {
+            // The opponent passes, or the game has ended.
+            self.negamax(child, side, num_eval, alpha, beta, ply).0
+        }
+    }
+
+    // The evaluator need not be zero-sum (e.g. `EvenEvaluator`), so the
+    // opponent's score is the negated score of this player, as in minimax.
+    fn evaluate(&self, board: &Board, side: Side) -> Score {
+        let score = self.evaluator.evaluate(board, self.side);
+        if side == self.side {
+            score
+        } else {
+            -score
+        }
+    }
+
+    fn record_cutoff(&mut self, board: &Board, pt: Point, num_eval: f64, ply: usize) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None, None]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(pt) {
+            killers[1] = killers[0];
+            killers[0] = Some(pt);
+        }
+
+        self.history[pt.offset(board.size()) as usize] += num_eval as u64;
+    }
+
+    fn ordered_children(
+        &mut self,
+        board: &mut Board,
+        side: Side,
+        num_eval: f64,
+        ply: usize,
+    ) -> Vec<Point> {
+        let mut children = self.sorted_children(board, side, num_eval, ply);
+        // Each helper thread starts from a different root move.
+        if ply == 0 && self.helper > 0 {
+            let len = children.len();
+            children.rotate_left(self.helper % len);
+        }
+        children
+    }
+
+    fn sorted_children(
+        &mut self,
+        board: &mut Board,
+        side: Side,
+        num_eval: f64,
+        ply: usize,
+    ) -> Vec<Point> {
+        let size = board.size();
+        let mut children = board.move_candidates().points(size).collect::<Vec<_>>();
+        if self.ordering == MoveOrdering::Natural {
+            return children;
+        }
+
+        let tt_move = self.table.best_move(board);
+
+        let (shallow_min, shallow_budget) = if self.limit.depth_limited {
+            (SHALLOW_ORDERING_MIN_DEPTH, SHALLOW_ORDERING_DEPTH)
+        } else {
+            (SHALLOW_ORDERING_MIN_EVAL, SHALLOW_ORDERING_EVAL)
+        };
+        if num_eval >= shallow_min {
+            let mut scored = children
+                .into_iter()
+                .map(|pt| {
+                    let undo = board.play(pt).unwrap();
+                    let score = self.child_score(
+                        board,
+                        side,
+                        shallow_budget,
+                        MIN_SCORE,
+                        MAX_SCORE,
+                        ply + 1,
+                    );
+                    board.undo(undo);
+                    (pt, score)
+                })
+                .collect::<Vec<_>>();
+            scored.sort_by(|a, b| {
+                (Some(b.0) == tt_move)
+                    .cmp(&(Some(a.0) == tt_move))
+                    .then(b.1.cmp(&a.1))
+            });
+            return scored.into_iter().map(|(pt, _)| pt).collect();
+        }
+
+        let killers = self.killers.get(ply).copied().unwrap_or([None, None]);
+        let history = &self.history;
+        children.sort_by_cached_key(|&pt| {
+            let is_tt = Some(pt) == tt_move;
+            let is_killer = killers.contains(&Some(pt));
+            // Moves leaving the opponent with few replies (or forcing a pass)
+            // come first. The replies are counted without settling the turn,
+            // as a pass leaves none either.
+            let undo = board.play(pt).unwrap();
+            let opp_mobility = board.move_candidates().num_bits();
+            board.undo(undo);
+            Reverse((
+                is_tt,
+                is_killer,
+                is_corner(pt, size),
+                Reverse(opp_mobility),
+                history[pt.offset(size) as usize],
+            ))
+        });
+        children
+    }
+}
+
+fn is_corner(pt: Point, size: Size) -> bool {
+    (pt.0 == 0 || pt.0 == size.0 - 1) && (pt.1 == 0 || pt.1 == size.1 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{EvenEvaluator, StrongEvaluator},
+        tool::{check_search, random_positions},
+    };
+
+    #[test]
+    fn same_scores_as_minimax() {
+        for &size in &[Size(4, 4), Size(5, 6), Size(6, 6)] {
+            let positions = random_positions(&Board::new(size), 1);
+            let errors = check_search(&positions, 2_000, 1, || StrongEvaluator::new(size));
+            assert!(errors.is_empty(), "{}x{}: {:?}", size.0, size.1, errors);
+            let errors = check_search(&positions, 2_000, 1, || EvenEvaluator::new(size));
+            assert!(errors.is_empty(), "{}x{}: {:?}", size.0, size.1, errors);
         }
     }
 }
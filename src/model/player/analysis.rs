@@ -0,0 +1,166 @@
+use super::{strength, AlphaBetaPlayer, Budget, Score, StrongEvaluator};
+use crate::model::{record, Board, GameRecord, Move, Point, Side};
+use std::{fmt, iter};
+
+// Score losses from which a move is an inaccuracy and a blunder, in
+// evaluator units for running scores and in disks for exact ones.
+const RUNNING_THRESHOLDS: (f64, f64) = (0.3, 1.0);
+const EXACT_THRESHOLDS: (f64, f64) = (2.0, 6.0);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Grade {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl AsRef<str> for Grade {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Grade::Best => "best",
+            Grade::Good => "good",
+            Grade::Inaccuracy => "inaccuracy",
+            Grade::Blunder => "blunder",
+        }
+    }
+}
+
+impl Grade {
+    /// Annotation symbol following the move, as in chess notation.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Grade::Best | Grade::Good => "",
+            Grade::Inaccuracy => "?!",
+            Grade::Blunder => "??",
+        }
+    }
+}
+
+/// A move of a game compared with the best move of its position, scores
+/// being from the view of the side to move.
+#[derive(Copy, Clone, Debug)]
+pub struct MoveAnalysis {
+    pub side: Side,
+    pub pt: Point,
+    pub score: Score,
+    pub best: Point,
+    pub best_score: Score,
+    pub grade: Grade,
+}
+
+impl MoveAnalysis {
+    /// How much worse the move is than the best one.
+    pub fn loss(&self) -> f64 {
+        strength::value(self.best_score) - strength::value(self.score)
+    }
+}
+
+impl fmt::Display for MoveAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {}{} {}",
+            self.side,
+            record::format_point(self.pt),
+            self.grade.symbol(),
+            format_score(self.score)
+        )?;
+        if self.grade != Grade::Best {
+            write!(
+                f,
+                ", best {} {}, loss {:.2}",
+                record::format_point(self.best),
+                format_score(self.best_score),
+                self.loss()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Searches the position before every disk placed in `record` to `depth`,
+/// or to the end of the game once at most `exact_empties` cells are empty,
+/// and grades the moves played against the best ones. Passes are left out.
+/// Stops at the first illegal move.
+pub fn analyze(
+    record: &GameRecord,
+    depth: u32,
+    exact_empties: u32,
+) -> impl Iterator<Item = MoveAnalysis> + '_ {
+    let mut board = record.initial_board();
+    let mut moves = record.moves.iter();
+    iter::from_fn(move || loop {
+        let mv = *moves.next()?;
+        let analysis = match mv {
+            Move::Place(pt) => Some(analyze_move(&board, pt, depth, exact_empties)?),
+            Move::Pass => None,
+        };
+        board = board.apply(mv)?;
+        if analysis.is_some() {
+            return analysis;
+        }
+    })
+}
+
+/// Grades the move `pt` on `board`, or returns `None` if the move is
+/// illegal.
+fn analyze_move(board: &Board, pt: Point, depth: u32, exact_empties: u32) -> Option<MoveAnalysis> {
+    let side = board.turn()?;
+    let size = board.size();
+    let num_empty = board.num_empty();
+    // Passes do not count as plies, so searching as deep as there are empty
+    // cells reaches the end of the game.
+    let depth = if num_empty <= exact_empties {
+        num_empty
+    } else {
+        depth
+    };
+    let mut player =
+        AlphaBetaPlayer::new(side, 0, StrongEvaluator::new(size)).budget(Budget::Depth(depth));
+    let moves = player.score_moves(board);
+    let score = moves.iter().find(|&&(p, _)| p == pt)?.1;
+    let (best, best_score) = *moves.iter().max_by_key(|&&(_, score)| score)?;
+
+    let (inaccuracy, blunder) = match (score, best_score) {
+        (Score::Ended(_), _) | (_, Score::Ended(_)) => EXACT_THRESHOLDS,
+        _ => RUNNING_THRESHOLDS,
+    };
+    let mut analysis = MoveAnalysis {
+        side,
+        pt,
+        score,
+        best,
+        best_score,
+        grade: Grade::Best,
+    };
+    let loss = analysis.loss();
+    analysis.grade = if pt == best || loss <= 0.0 {
+        Grade::Best
+    } else if loss < inaccuracy {
+        Grade::Good
+    } else if loss < blunder {
+        Grade::Inaccuracy
+    } else {
+        Grade::Blunder
+    };
+    Some(analysis)
+}
+
+/// The text form of `record` with one comment line per analysed move.
+pub fn annotate(record: &GameRecord, analyses: &[MoveAnalysis]) -> String {
+    let mut text = record.to_string();
+    for (i, analysis) in analyses.iter().enumerate() {
+        text += &format!("# {:>3}. {}\n", i + 1, analysis);
+    }
+    text
+}
+
+fn format_score(score: Score) -> String {
+    match score {
+        Score::Running(v) => format!("{:+.2}", v),
+        Score::Ended(v) => format!("{:+} disks", v),
+        Score::Infinity => "+inf".to_string(),
+        Score::NegInfinity => "-inf".to_string(),
+    }
+}
@@ -0,0 +1,96 @@
+use crate::model::{Board, Point, Size, Symmetry};
+use rand::{seq::SliceRandom as _, Rng};
+use std::collections::HashMap;
+
+// Well-known 8x8 opening lines in standard notation, columns a-h and rows 1-8.
+const LINES: &[&str] = &[
+    // tiger
+    "f5d6c3d3c4",
+    // rose
+    "f5d6c5f4e3",
+    // buffalo
+    "f5f6e6f4c3",
+    // heath
+    "f5f6e6f4g5",
+    // stephenson
+    "f5f6e6f4e3",
+];
+
+/// Opening book, mapping positions to the moves recommended there. Both are
+/// kept in the frame of the canonical position, so that the book covers the
+/// positions symmetric to the lines as well.
+#[derive(Clone, Debug)]
+pub struct Book {
+    moves: HashMap<u64, Vec<Point>>,
+}
+
+impl Book {
+    /// The book for boards of `size`, which is empty except on 8x8 boards.
+    pub fn new(size: Size) -> Self {
+        let mut moves = HashMap::new();
+        if size == Size(8, 8) {
+            for line in LINES {
+                add_line(&mut moves, size, parse_line(line));
+            }
+        }
+        Book { moves }
+    }
+
+    /// One of the book moves for `board`, chosen at random.
+    pub fn choose<R>(&self, board: &Board, rng: &mut R) -> Option<Point>
+    where
+        R: Rng,
+    {
+        self.moves(board).choose(rng).copied()
+    }
+
+    /// The book moves for `board`, none if it is out of the book.
+    pub fn moves(&self, board: &Board) -> Vec<Point> {
+        let size = board.size();
+        let (canonical, _) = board.canonical();
+        let stored = match self.moves.get(&canonical.hash_key()) {
+            Some(stored) => stored,
+            None => return vec![],
+        };
+        // A position symmetric in itself, like the starting one, has the
+        // images of the stored moves under all its symmetries.
+        let mut moves = vec![];
+        for &sym in Symmetry::of_size(size) {
+            if board.transform(sym) != canonical {
+                continue;
+            }
+            for &pt in stored {
+                let pt = sym.inverse().apply(pt, sym.apply_size(size));
+                if board.move_candidates().contains(pt, size) && !moves.contains(&pt) {
+                    moves.push(pt);
+                }
+            }
+        }
+        moves
+    }
+}
+
+fn add_line<I>(moves: &mut HashMap<u64, Vec<Point>>, size: Size, line: I)
+where
+    I: Iterator<Item = Point>,
+{
+    let mut board = Board::new(size);
+    for pt in line {
+        let next = board
+            .make_move(pt)
+            .unwrap_or_else(|| panic!("illegal book move: {:?}", pt));
+        let (canonical, sym) = board.canonical();
+        let pt = sym.apply(pt, size);
+        let pts = moves.entry(canonical.hash_key()).or_default();
+        if !pts.contains(&pt) {
+            pts.push(pt);
+        }
+        board = next;
+    }
+}
+
+fn parse_line(line: &str) -> impl Iterator<Item = Point> + '_ {
+    line.as_bytes()
+        .chunks(2)
+        .map(|mv| Point(u32::from(mv[0] - b'a'), u32::from(mv[1] - b'1')))
+}
@@ -0,0 +1,315 @@
+use super::{PatternEvaluator, Strength};
+use crate::model::Size;
+use std::{fmt, str::FromStr, time::Duration};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlayerKind {
+    Human,
+    Ai(AiConfig),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AiConfig {
+    pub algorithm: Algorithm,
+    pub evaluator: EvaluatorKind,
+    pub budget: Budget,
+    pub strength: Strength,
+    pub book: bool,
+    pub num_threads: usize,
+    pub ponder: bool,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Algorithm {
+    Random,
+    AlphaBeta,
+    Mcts,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EvaluatorKind {
+    Strong,
+    Pattern,
+    Even,
+    Weak,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Budget {
+    /// Evaluated positions for alpha-beta, playouts for MCTS.
+    Nodes(u32),
+    Time(Duration),
+    Depth(u32),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BudgetKind {
+    Nodes,
+    Time,
+    Depth,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub config: AiConfig,
+}
+
+impl Default for PlayerKind {
+    fn default() -> PlayerKind {
+        PlayerKind::Human
+    }
+}
+
+impl PlayerKind {
+    pub fn name(&self) -> String {
+        match self {
+            PlayerKind::Human => "Human".to_string(),
+            PlayerKind::Ai(config) => format!("AI: {}", config.preset_name().unwrap_or("custom")),
+        }
+    }
+}
+
+impl Preset {
+    /// Name usable on the command line, `alpha-beta-strong-s` for the preset
+    /// "alpha-beta strong S".
+    pub fn slug(&self) -> String {
+        self.name.to_lowercase().replace(' ', "-")
+    }
+}
+
+impl AiConfig {
+    pub fn presets() -> Vec<Preset> {
+        use self::{Algorithm::*, EvaluatorKind::*};
+        let config = |algorithm, evaluator, num_eval| AiConfig {
+            algorithm,
+            evaluator,
+            budget: Budget::Nodes(num_eval),
+            strength: Strength::Full,
+            book: false,
+            num_threads: 1,
+            ponder: false,
+        };
+        let preset = |name, config| Preset { name, config };
+        // Strength ladder from beginner to expert
+        let level = |name, temperature| Preset {
+            name,
+            config: AiConfig {
+                strength: Strength::Softmax(temperature),
+                ..config(AlphaBeta, Strong, 100_000)
+            },
+        };
+        vec![
+            preset("random", config(Random, Strong, 0)),
+            level("level 1", 2.0),
+            level("level 2", 1.0),
+            level("level 3", 0.5),
+            level("level 4", 0.25),
+            level("level 5", 0.1),
+            preset(
+                "coach",
+                AiConfig {
+                    strength: Strength::Coach,
+                    ..config(AlphaBeta, Strong, 100_000)
+                },
+            ),
+            preset("alpha-beta strong S", config(AlphaBeta, Strong, 1_000_000)),
+            preset("alpha-beta strong M", config(AlphaBeta, Strong, 10_000_000)),
+            preset(
+                "alpha-beta strong L",
+                config(AlphaBeta, Strong, 100_000_000),
+            ),
+            preset(
+                "alpha-beta pattern S",
+                config(AlphaBeta, Pattern, 1_000_000),
+            ),
+            preset(
+                "alpha-beta pattern M",
+                config(AlphaBeta, Pattern, 10_000_000),
+            ),
+            preset(
+                "alpha-beta pattern L",
+                config(AlphaBeta, Pattern, 100_000_000),
+            ),
+            preset("alpha-beta even S", config(AlphaBeta, Even, 1_000_000)),
+            preset("alpha-beta even M", config(AlphaBeta, Even, 10_000_000)),
+            preset("alpha-beta even L", config(AlphaBeta, Even, 100_000_000)),
+            preset("alpha-beta weak S", config(AlphaBeta, Weak, 1_000_000)),
+            preset("alpha-beta weak M", config(AlphaBeta, Weak, 10_000_000)),
+            preset("alpha-beta weak L", config(AlphaBeta, Weak, 100_000_000)),
+            preset("MCTS S", config(Mcts, Strong, 10_000)),
+            preset("MCTS M", config(Mcts, Strong, 100_000)),
+            preset("MCTS L", config(Mcts, Strong, 1_000_000)),
+        ]
+    }
+
+    /// Name of the preset playing like this configuration. Threads and
+    /// pondering are ignored as they only change the thinking time.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        let normalize = |config: &AiConfig| AiConfig {
+            num_threads: 1,
+            ponder: false,
+            ..*config
+        };
+        let config = normalize(self);
+        Self::presets()
+            .into_iter()
+            .find(|preset| normalize(&preset.config) == config)
+            .map(|preset| preset.name)
+    }
+
+    pub fn from_slug(slug: &str) -> Option<AiConfig> {
+        Self::presets()
+            .into_iter()
+            .find(|preset| preset.slug() == slug)
+            .map(|preset| preset.config)
+    }
+}
+
+impl AsRef<str> for Algorithm {
+    fn as_ref(&self) -> &str {
+        use self::Algorithm::*;
+        match *self {
+            Random => "random",
+            AlphaBeta => "alpha-beta",
+            Mcts => "MCTS",
+        }
+    }
+}
+
+impl Algorithm {
+    pub fn all_values() -> [Self; 3] {
+        use self::Algorithm::*;
+        [Random, AlphaBeta, Mcts]
+    }
+
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+
+    pub fn budget_kinds(self) -> &'static [BudgetKind] {
+        use self::{Algorithm::*, BudgetKind::*};
+        match self {
+            Random => &[],
+            AlphaBeta => &[Nodes, Time, Depth],
+            Mcts => &[Nodes, Time],
+        }
+    }
+}
+
+impl AsRef<str> for EvaluatorKind {
+    fn as_ref(&self) -> &str {
+        use self::EvaluatorKind::*;
+        match *self {
+            Strong => "strong",
+            Pattern => "pattern",
+            Even => "even",
+            Weak => "weak",
+        }
+    }
+}
+
+impl EvaluatorKind {
+    pub fn all_values() -> [Self; 4] {
+        use self::EvaluatorKind::*;
+        [Strong, Pattern, Even, Weak]
+    }
+
+    /// Whether players with this evaluator can be made for boards of `size`,
+    /// the pattern evaluator needing trained weights for the size.
+    pub fn is_available(self, size: Size) -> bool {
+        match self {
+            EvaluatorKind::Pattern => PatternEvaluator::has_default(size),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Budget::Nodes(n) if n >= 1_000_000 && n % 1_000_000 == 0 => {
+                write!(f, "{}M nodes", n / 1_000_000)
+            }
+            Budget::Nodes(n) if n >= 1_000 && n % 1_000 == 0 => write!(f, "{}k nodes", n / 1_000),
+            Budget::Nodes(n) => write!(f, "{} nodes", n),
+            Budget::Time(time) => write!(f, "{} s", time.as_secs_f64()),
+            Budget::Depth(depth) => write!(f, "depth {}", depth),
+        }
+    }
+}
+
+// `nodes:N`, `time:MILLIS` or `depth:N`
+impl FromStr for Budget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid budget: {}", s);
+        let mut it = s.splitn(2, ':');
+        let kind = it.next().unwrap();
+        let value = it
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or_else(invalid)?;
+        match kind {
+            "nodes" => Ok(Budget::Nodes(value)),
+            "time" => Ok(Budget::Time(Duration::from_millis(u64::from(value)))),
+            "depth" => Ok(Budget::Depth(value)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Budget {
+    /// The budget in the form parsed by `from_str`.
+    pub fn spec(self) -> String {
+        match self {
+            Budget::Nodes(n) => format!("nodes:{}", n),
+            Budget::Time(time) => format!("time:{}", time.as_millis()),
+            Budget::Depth(depth) => format!("depth:{}", depth),
+        }
+    }
+
+    pub fn kind(self) -> BudgetKind {
+        match self {
+            Budget::Nodes(_) => BudgetKind::Nodes,
+            Budget::Time(_) => BudgetKind::Time,
+            Budget::Depth(_) => BudgetKind::Depth,
+        }
+    }
+}
+
+impl AsRef<str> for BudgetKind {
+    fn as_ref(&self) -> &str {
+        use self::BudgetKind::*;
+        match *self {
+            Nodes => "nodes",
+            Time => "time",
+            Depth => "depth",
+        }
+    }
+}
+
+impl BudgetKind {
+    pub fn choices(self) -> Vec<Budget> {
+        match self {
+            BudgetKind::Nodes => [1_000, 10_000, 100_000, 1_000_000, 10_000_000, 100_000_000]
+                .iter()
+                .map(|&n| Budget::Nodes(n))
+                .collect(),
+            BudgetKind::Time => [100, 500, 1_000, 2_000, 5_000, 10_000, 30_000]
+                .iter()
+                .map(|&ms| Budget::Time(Duration::from_millis(ms)))
+                .collect(),
+            BudgetKind::Depth => (1..=12).map(Budget::Depth).collect(),
+        }
+    }
+
+    pub fn default_budget(self) -> Budget {
+        match self {
+            BudgetKind::Nodes => Budget::Nodes(1_000_000),
+            BudgetKind::Time => Budget::Time(Duration::from_secs(1)),
+            BudgetKind::Depth => Budget::Depth(6),
+        }
+    }
+}
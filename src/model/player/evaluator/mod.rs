@@ -1,11 +1,14 @@
 pub use self::{
-    even::Evaluator as EvenEvaluator, strong::Evaluator as StrongEvaluator,
+    even::Evaluator as EvenEvaluator,
+    pattern::{Evaluator as PatternEvaluator, Patterns, Weights as PatternWeights},
+    strong::Evaluator as StrongEvaluator,
     weak::Evaluator as WeakEvaluator,
 };
 use crate::model::{Board, Side};
-use std::{cmp::Ordering, f64, i32};
+use std::{cmp::Ordering, f64, i32, ops::Neg};
 
 mod even;
+mod pattern;
 mod strong;
 mod weak;
 
@@ -13,6 +16,12 @@ pub trait Evaluate {
     fn evaluate(&self, board: &Board, myside: Side) -> Score;
 }
 
+// Final disk difference as the rules count it, for boards where the game has
+// ended.
+fn ended_score(board: &Board, myside: Side) -> Score {
+    Score::Ended(board.outcome(myside))
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Score {
     NegInfinity,
@@ -24,6 +33,48 @@ pub enum Score {
 pub const MIN_SCORE: Score = Score::NegInfinity;
 pub const MAX_SCORE: Score = Score::Infinity;
 
+impl Score {
+    /// Returns the smallest score greater than `self`.
+    pub fn next_up(self) -> Score {
+        match self {
+            Score::NegInfinity => Score::Ended(i32::MIN),
+            Score::Infinity => Score::Infinity,
+            Score::Running(v) if v == f64::INFINITY => Score::Ended(1),
+            Score::Running(v) => Score::Running(next_up_f64(v)),
+            // Every running score lies between `Ended(-1)` and `Ended(1)`.
+            Score::Ended(-1) => Score::Running(f64::NEG_INFINITY),
+            Score::Ended(0) => Score::Running(next_up_f64(0.0)),
+            Score::Ended(i32::MAX) => Score::Infinity,
+            Score::Ended(v) => Score::Ended(v + 1),
+        }
+    }
+}
+
+fn next_up_f64(v: f64) -> f64 {
+    if v.is_nan() || v == f64::INFINITY {
+        v
+    } else if v == 0.0 {
+        f64::from_bits(1)
+    } else if v > 0.0 {
+        f64::from_bits(v.to_bits() + 1)
+    } else {
+        f64::from_bits(v.to_bits() - 1)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        match self {
+            Score::NegInfinity => Score::Infinity,
+            Score::Infinity => Score::NegInfinity,
+            Score::Running(v) => Score::Running(-v),
+            Score::Ended(v) => Score::Ended(-v),
+        }
+    }
+}
+
 impl PartialEq for Score {
     fn eq(&self, other: &Score) -> bool {
         match (*self, *other) {
@@ -79,3 +130,30 @@ impl Ord for Score {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_up_is_greater() {
+        let scores = [
+            Score::NegInfinity,
+            Score::Ended(i32::MIN),
+            Score::Ended(-1),
+            Score::Running(f64::NEG_INFINITY),
+            Score::Running(-1.5),
+            Score::Running(0.0),
+            Score::Running(2.0),
+            Score::Running(f64::INFINITY),
+            Score::Ended(0),
+            Score::Ended(1),
+            Score::Ended(i32::MAX),
+        ];
+        for &score in &scores {
+            assert!(score.next_up() > score, "{:?}", score);
+        }
+        assert_eq!(Score::Running(f64::INFINITY).next_up(), Score::Ended(1));
+        assert!(matches!(Score::Ended(i32::MAX).next_up(), Score::Infinity));
+    }
+}
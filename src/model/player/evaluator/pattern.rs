@@ -0,0 +1,446 @@
+use super::{ended_score, strong::misere_black_score, Evaluate, Score};
+use crate::model::{Board, Point, Rules, Side, Size};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+// Weight file layout (all values little endian):
+//
+//   magic "OTPW", version: u32, cols: u32, rows: u32, num_phases: u32,
+//   per phase: per pattern family: 3^len x f32, then mobility: f32, bias: f32
+const MAGIC: &[u8; 4] = b"OTPW";
+const VERSION: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub struct Evaluator {
+    patterns: Patterns,
+    weights: Weights,
+}
+
+impl Evaluate for Evaluator {
+    fn evaluate(&self, board: &Board, myside: Side) -> Score {
+        if board.turn().is_none() {
+            return ended_score(board, myside);
+        }
+        // The weights are fitted to standard games.
+        let black_score = match board.rules() {
+            Rules::Standard => f64::from(self.black_score(board)),
+            Rules::Misere => misere_black_score(board),
+        };
+        let score = match myside {
+            Side::Black => black_score,
+            Side::White => -black_score,
+        };
+        Score::Running(score)
+    }
+}
+
+impl Evaluator {
+    pub fn load(path: &Path, size: Size) -> io::Result<Self> {
+        let patterns = Patterns::new(size);
+        let weights = Weights::parse(&fs::read(path)?, &patterns)?;
+        Ok(Evaluator { patterns, weights })
+    }
+
+    /// `weights/pattern-<cols>x<rows>.bin`, or the same file in
+    /// `$OTHELLO_WEIGHTS_DIR` if set.
+    pub fn default_path(size: Size) -> PathBuf {
+        let dir = env::var_os("OTHELLO_WEIGHTS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("weights"));
+        dir.join(format!("pattern-{}x{}.bin", size.0, size.1))
+    }
+
+    /// The weights of `default_path`, with the path in the error.
+    pub fn load_default(size: Size) -> Result<Self, String> {
+        let path = Self::default_path(size);
+        Self::load(&path, size).map_err(|e| format!("cannot load {}: {}", path.display(), e))
+    }
+
+    /// Whether `load_default` succeeds, tried once per size.
+    pub fn has_default(size: Size) -> bool {
+        static LOADED: OnceLock<Mutex<HashMap<Size, bool>>> = OnceLock::new();
+        let mut loaded = LOADED.get_or_init(Default::default).lock().unwrap();
+        *loaded
+            .entry(size)
+            .or_insert_with(|| Self::load_default(size).is_ok())
+    }
+
+    fn black_score(&self, board: &Board) -> f32 {
+        self.weights.predict(&self.patterns, board)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Patterns {
+    size: Size,
+    families: Vec<Family>,
+}
+
+// Instances of a family are images of each other under the board's
+// symmetries and share one weight table.
+#[derive(Clone, Debug)]
+struct Family {
+    len: u32,
+    instances: Vec<Vec<u32>>,
+}
+
+impl Patterns {
+    pub fn new(size: Size) -> Self {
+        let (w, h) = (size.0, size.1);
+        let corners = [
+            (0, 0, 1, 1),
+            (w - 1, 0, -1, 1),
+            (0, h - 1, 1, -1),
+            (w - 1, h - 1, -1, -1),
+        ];
+        let cell = |x: u32, y: u32| Point(x, y).offset(size);
+        // Cells at (along, across) from corner `c`, `along` being horizontal
+        // unless `transpose` is set.
+        let corner_cells = |c: (u32, u32, i32, i32), along: u32, across: u32, transpose: bool| {
+            let mut cells = vec![];
+            for j in 0..across {
+                for i in 0..along {
+                    let (i, j) = if transpose { (j, i) } else { (i, j) };
+                    let x = (c.0 as i32 + c.2 * i as i32) as u32;
+                    let y = (c.1 as i32 + c.3 * j as i32) as u32;
+                    cells.push(cell(x, y));
+                }
+            }
+            cells
+        };
+
+        let mut families = vec![];
+
+        let top_bottom = vec![
+            (0..w).map(|x| cell(x, 0)).collect(),
+            (0..w).map(|x| cell(x, h - 1)).collect(),
+        ];
+        let left_right = vec![
+            (0..h).map(|y| cell(0, y)).collect::<Vec<_>>(),
+            (0..h).map(|y| cell(w - 1, y)).collect(),
+        ];
+        if w == h {
+            families.push(Family::new(
+                top_bottom.into_iter().chain(left_right).collect(),
+            ));
+        } else {
+            families.push(Family::new(top_bottom));
+            families.push(Family::new(left_right));
+        }
+
+        if w >= 3 && h >= 3 {
+            let instances = corners
+                .iter()
+                .map(|&c| corner_cells(c, 3, 3, false))
+                .collect();
+            families.push(Family::new(instances));
+        }
+
+        // Lying and standing 2x5 blocks are images of each other only on
+        // square boards.
+        let lying = corners
+            .iter()
+            .filter(|_| w >= 5)
+            .map(|&c| corner_cells(c, 5, 2, false))
+            .collect::<Vec<_>>();
+        let standing = corners
+            .iter()
+            .filter(|_| h >= 5)
+            .map(|&c| corner_cells(c, 5, 2, true))
+            .collect::<Vec<_>>();
+        if w == h {
+            if !lying.is_empty() {
+                families.push(Family::new(lying.into_iter().chain(standing).collect()));
+            }
+        } else {
+            for instances in [lying, standing] {
+                if !instances.is_empty() {
+                    families.push(Family::new(instances));
+                }
+            }
+        }
+
+        let len = u32::min(w, h);
+        if len >= 4 {
+            let instances = corners
+                .iter()
+                .map(|&c| {
+                    (0..len)
+                        .map(|i| {
+                            let x = (c.0 as i32 + c.2 * i as i32) as u32;
+                            let y = (c.1 as i32 + c.3 * i as i32) as u32;
+                            cell(x, y)
+                        })
+                        .collect()
+                })
+                .collect();
+            families.push(Family::new(instances));
+        }
+
+        Patterns { size, families }
+    }
+
+    fn table_lens(&self) -> impl Iterator<Item = usize> + '_ {
+        self.families.iter().map(|f| 3usize.pow(f.len))
+    }
+}
+
+impl Family {
+    // Instances covering the cells of an earlier one are dropped, as where
+    // the diagonals from opposite corners of a square board are the same.
+    fn new(instances: Vec<Vec<u32>>) -> Self {
+        let len = instances[0].len() as u32;
+        assert!(instances.iter().all(|cells| cells.len() as u32 == len));
+        let mut cell_sets: Vec<Vec<u32>> = vec![];
+        let mut unique = vec![];
+        for cells in instances {
+            let mut set = cells.clone();
+            set.sort_unstable();
+            if !cell_sets.contains(&set) {
+                cell_sets.push(set);
+                unique.push(cells);
+            }
+        }
+        Family {
+            len,
+            instances: unique,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Weights {
+    size: Size,
+    phases: Vec<PhaseWeights>,
+}
+
+#[derive(Clone, Debug)]
+struct PhaseWeights {
+    tables: Vec<Vec<f32>>,
+    mobility: f32,
+    bias: f32,
+}
+
+impl Weights {
+    pub fn zero(patterns: &Patterns, num_phases: u32) -> Self {
+        assert!(num_phases > 0);
+        let phase = PhaseWeights {
+            tables: patterns.table_lens().map(|len| vec![0.0; len]).collect(),
+            mobility: 0.0,
+            bias: 0.0,
+        };
+        Weights {
+            size: patterns.size,
+            phases: vec![phase; num_phases as usize],
+        }
+    }
+
+    /// Estimated final disk difference of `board` for black.
+    pub fn predict(&self, patterns: &Patterns, board: &Board) -> f32 {
+        let phase = &self.phases[self.phase(board)];
+        let mut score = phase.bias + phase.mobility * mobility(board);
+        for (family, table) in patterns.families.iter().zip(&phase.tables) {
+            for cells in &family.instances {
+                score += table[pattern_index(board, cells)];
+            }
+        }
+        score
+    }
+
+    /// Moves the prediction for `board` towards `target` by a normalised
+    /// least mean squares step, and returns the error before the step.
+    pub fn train(&mut self, patterns: &Patterns, board: &Board, target: f32, rate: f32) -> f32 {
+        let error = target - self.predict(patterns, board);
+        let mobility = mobility(board);
+        let num_instances = patterns
+            .families
+            .iter()
+            .map(|f| f.instances.len())
+            .sum::<usize>();
+        let step = rate * error / (num_instances as f32 + 1.0 + mobility * mobility);
+
+        let phase = self.phase(board);
+        let phase = &mut self.phases[phase];
+        phase.bias += step;
+        phase.mobility += step * mobility;
+        for (family, table) in patterns.families.iter().zip(&mut phase.tables) {
+            for cells in &family.instances {
+                table[pattern_index(board, cells)] += step;
+            }
+        }
+        error
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = MAGIC.to_vec();
+        let header = [VERSION, self.size.0, self.size.1, self.phases.len() as u32];
+        for &v in &header {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        for phase in &self.phases {
+            let scalars = [phase.mobility, phase.bias];
+            for v in phase.tables.iter().flatten().chain(&scalars) {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        fs::write(path, bytes)
+    }
+
+    // Phases split the game evenly by the number of disks on the board.
+    fn phase(&self, board: &Board) -> usize {
+        let size = board.size();
+        let num_disk = board.num_disk(Side::Black) + board.num_disk(Side::White);
+        let num_cell = size.0 * size.1;
+        let phases = self.phases.len() as u32;
+        let phase = (num_disk.saturating_sub(4) * phases) / (num_cell - 3);
+        u32::min(phase, phases - 1) as usize
+    }
+
+    fn parse(bytes: &[u8], patterns: &Patterns) -> io::Result<Self> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(invalid_data("not a pattern weight file"));
+        }
+        if reader.u32()? != VERSION {
+            return Err(invalid_data("unsupported weight file version"));
+        }
+        let size = Size(reader.u32()?, reader.u32()?);
+        if size != patterns.size {
+            return Err(invalid_data(&format!(
+                "weights are for {}x{} boards",
+                size.0, size.1
+            )));
+        }
+        let num_phases = reader.u32()?;
+        if num_phases == 0 {
+            return Err(invalid_data("no phases in weight file"));
+        }
+
+        let mut phases = vec![];
+        for _ in 0..num_phases {
+            let tables = patterns
+                .table_lens()
+                .map(|len| (0..len).map(|_| reader.f32()).collect())
+                .collect::<io::Result<_>>()?;
+            phases.push(PhaseWeights {
+                tables,
+                mobility: reader.f32()?,
+                bias: reader.f32()?,
+            });
+        }
+        if !reader.bytes.is_empty() {
+            return Err(invalid_data("trailing data in weight file"));
+        }
+
+        Ok(Weights { size, phases })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "weight file is truncated",
+            ));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+// Base 3 number whose digits are the cells' states: 0 empty, 1 black, 2 white.
+fn pattern_index(board: &Board, cells: &[u32]) -> usize {
+    let black = board.black_cells().bits();
+    let white = board.white_cells().bits();
+    cells.iter().rev().fold(0, |idx, &off| {
+        let digit = (black >> off & 1) + 2 * (white >> off & 1);
+        idx * 3 + digit as usize
+    })
+}
+
+fn mobility(board: &Board) -> f32 {
+    let num_cand = board.move_candidates().num_bits() as f32;
+    match board.turn() {
+        Some(Side::Black) => num_cand,
+        Some(Side::White) => -num_cand,
+        None => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Symmetry, MAX_SIZE, MIN_SIZE};
+
+    // The cells of each instance as a sorted set, so that instances reading
+    // the same cells in another order compare equal.
+    fn cell_sets(family: &Family) -> Vec<Vec<u32>> {
+        family
+            .instances
+            .iter()
+            .map(|cells| {
+                let mut cells = cells.clone();
+                cells.sort_unstable();
+                cells
+            })
+            .collect()
+    }
+
+    #[test]
+    fn families_are_symmetry_classes() {
+        for rows in MIN_SIZE..=MAX_SIZE {
+            for cols in MIN_SIZE..=MAX_SIZE {
+                let size = Size(cols, rows);
+                let image = |sym: Symmetry, cells: &[u32]| {
+                    let mut image = cells
+                        .iter()
+                        .map(|&off| sym.apply(Point(off % cols, off / cols), size).offset(size))
+                        .collect::<Vec<_>>();
+                    image.sort_unstable();
+                    image
+                };
+                for family in &Patterns::new(size).families {
+                    let sets = cell_sets(family);
+                    for (i, cells) in sets.iter().enumerate() {
+                        assert!(!sets[..i].contains(cells), "{:?}: duplicate", size);
+                    }
+                    // Exactly the images of the first instance.
+                    let mut orbit = Symmetry::of_size(size)
+                        .iter()
+                        .map(|&sym| image(sym, &sets[0]))
+                        .collect::<Vec<_>>();
+                    orbit.sort();
+                    orbit.dedup();
+                    let mut sets = sets;
+                    sets.sort();
+                    assert_eq!(sets, orbit, "{:?}", size);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
-use super::{Evaluate, Score};
-use crate::model::{BitBoard, Board, Point, Side, Size};
+use super::{ended_score, Evaluate, Score};
+use crate::model::{BitBoard, Board, Point, Rules, Side, Size};
 use std::{f64, i32};
 
 #[derive(Clone, Debug)]
@@ -11,27 +11,24 @@ impl Evaluate for Evaluator {
     fn evaluate(&self, board: &Board, myside: Side) -> Score {
         match board.turn() {
             Some(_) => {
-                let num_disk = f64::from((board.black_cells() | board.white_cells()).num_bits());
-                let disk_score = f64::from(self.eval_disk_place(board));
-                let cand_score = f64::from(self.eval_move_candidates(board));
-                // TODO: set appropriate score weights
-                let black_score = disk_score / num_disk + 0.1 * cand_score;
-                let score = match myside {
-                    Side::Black => black_score,
-                    Side::White => -black_score,
+                let black_score = match board.rules() {
+                    Rules::Standard => {
+                        let num_disk =
+                            f64::from((board.black_cells() | board.white_cells()).num_bits());
+                        let disk_score = f64::from(self.eval_disk_place(board));
+                        let cand_score = f64::from(eval_move_candidates(board));
+                        // TODO: set appropriate score weights
+                        disk_score / num_disk + 0.1 * cand_score
+                    }
+                    Rules::Misere => misere_black_score(board),
                 };
-                Score::Running(score)
-            }
-            None => {
-                let black = board.black_cells().num_bits() as i32;
-                let white = board.white_cells().num_bits() as i32;
-                let black_score = black - white;
                 let score = match myside {
                     Side::Black => black_score,
                     Side::White => -black_score,
                 };
-                Score::Ended(score)
+                Score::Running(score)
             }
+            None => ended_score(board, myside),
         }
     }
 }
@@ -100,14 +97,24 @@ impl Evaluator {
         }
         black - white
     }
+}
 
-    fn eval_move_candidates(&self, board: &Board) -> i32 {
-        let num_cand = board.move_candidates().num_bits() as i32;
-        match board.turn() {
-            Some(Side::Black) => num_cand,
-            Some(Side::White) => -num_cand,
-            None => 0,
-        }
+// Score for black of a running misère game. Corners and edges are no prize
+// when disks are a burden, so the weights give way to having few disks and
+// many moves.
+pub(super) fn misere_black_score(board: &Board) -> f64 {
+    let num_disk = f64::from((board.black_cells() | board.white_cells()).num_bits());
+    let disk_score = f64::from(board.outcome(Side::Black));
+    let cand_score = f64::from(eval_move_candidates(board));
+    disk_score / num_disk + 0.1 * cand_score
+}
+
+fn eval_move_candidates(board: &Board) -> i32 {
+    let num_cand = board.move_candidates().num_bits() as i32;
+    match board.turn() {
+        Some(Side::Black) => num_cand,
+        Some(Side::White) => -num_cand,
+        None => 0,
     }
 }
 
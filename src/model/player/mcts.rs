@@ -0,0 +1,229 @@
+use super::{FindMove, Score};
+use crate::model::{BitBoard, Board, Point, Rules, Side, Size};
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+use std::{
+    f64,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// Exploration constant of the UCT formula.
+const EXPLORATION: f64 = f64::consts::SQRT_2;
+
+#[derive(Debug)]
+pub struct Player {
+    num_playouts: u32,
+    time_limit: Option<Duration>,
+    abort: Arc<AtomicBool>,
+    rng: StdRng,
+}
+
+#[derive(Debug)]
+struct Node {
+    board: Board,
+    // move leading to this node from its parent
+    pt: Option<Point>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: BitBoard,
+    num_visits: u32,
+    // sum of the playout rewards for the side that moved into this node
+    reward: f64,
+}
+
+impl Player {
+    pub fn new(num_playouts: u32) -> Self {
+        Player {
+            num_playouts,
+            time_limit: None,
+            abort: Arc::new(AtomicBool::new(false)),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Stops the search after `limit` even if playouts remain.
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    pub fn abort_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.abort = flag;
+        self
+    }
+
+    /// Makes the playouts reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn is_stopped(&self, start: Instant) -> bool {
+        self.abort.load(Ordering::Relaxed)
+            || self
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+    }
+
+    // Grows the search tree from `board`, the root at index 0.
+    fn search(&mut self, board: Board) -> Vec<Node> {
+        let start = Instant::now();
+        let mut tree = vec![Node::new(board, None, None)];
+
+        for i in 0..self.num_playouts {
+            // Checking the clock on every playout is needlessly expensive.
+            if i % 64 == 0 && self.is_stopped(start) {
+                break;
+            }
+            let selected = select(&tree);
+            let leaf = expand(&mut tree, selected, &mut self.rng);
+            let winner = playout(tree[leaf].board, &mut self.rng);
+            backpropagate(&mut tree, leaf, winner);
+        }
+        tree
+    }
+}
+
+impl FindMove for Player {
+    fn find_move(&mut self, board: Board) -> Point {
+        let tree = self.search(board);
+        let root = &tree[0];
+        if root.children.is_empty() {
+            // Not even one playout ran.
+            let size = board.size();
+            return board.move_candidates().points(size).next().unwrap();
+        }
+        let best = root
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].num_visits)
+            .unwrap();
+        tree[*best].pt.unwrap()
+    }
+
+    // Win rates of the moves from -1 to 1, the moves left untried by the
+    // budget counting as lost.
+    fn score_moves(&mut self, board: &Board) -> Option<Vec<(Point, Score)>> {
+        let tree = self.search(*board);
+        let size = board.size();
+        let scores = board
+            .move_candidates()
+            .points(size)
+            .map(|pt| {
+                let child = tree[0]
+                    .children
+                    .iter()
+                    .map(|&child| &tree[child])
+                    .find(|node| node.pt == Some(pt));
+                let rate = child.map_or(0.0, |node| node.reward / f64::from(node.num_visits));
+                (pt, Score::Running(2.0 * rate - 1.0))
+            })
+            .collect();
+        Some(scores)
+    }
+}
+
+impl Node {
+    fn new(board: Board, pt: Option<Point>, parent: Option<usize>) -> Self {
+        Node {
+            board,
+            pt,
+            parent,
+            children: vec![],
+            untried: board.move_candidates(),
+            num_visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = f64::from(self.num_visits);
+        self.reward / visits + EXPLORATION * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+// Descends by UCT to a node with untried moves or an ended game.
+fn select(tree: &[Node]) -> usize {
+    let mut idx = 0;
+    loop {
+        let node = &tree[idx];
+        if !node.untried.is_empty() || node.children.is_empty() {
+            return idx;
+        }
+        idx = *node
+            .children
+            .iter()
+            .max_by(|&&a, &&b| {
+                let (a, b) = (tree[a].uct(node.num_visits), tree[b].uct(node.num_visits));
+                a.partial_cmp(&b).unwrap()
+            })
+            .unwrap();
+    }
+}
+
+fn expand(tree: &mut Vec<Node>, idx: usize, rng: &mut StdRng) -> usize {
+    let size = tree[idx].board.size();
+    let untried = tree[idx].untried;
+    if untried.is_empty() {
+        return idx;
+    }
+    let pt = random_point(untried, size, rng);
+    tree[idx].untried &= !BitBoard::from_point(pt, size);
+    let board = tree[idx].board.make_move(pt).unwrap();
+    let child = tree.len();
+    tree.push(Node::new(board, Some(pt), Some(idx)));
+    tree[idx].children.push(child);
+    child
+}
+
+// Plays random moves to the end of the game, always taking a corner when one
+// is available unless disks are a burden or the board has none, and returns
+// the winner.
+fn playout(mut board: Board, rng: &mut StdRng) -> Option<Side> {
+    let size = board.size();
+    let corners = match board.rules() {
+        Rules::Standard => board.corner_cells(),
+        Rules::Misere => BitBoard::empty(),
+    };
+
+    while board.turn().is_some() {
+        let cands = board.move_candidates();
+        let corner_cands = cands & corners;
+        let cands = if corner_cands.is_empty() {
+            cands
+        } else {
+            corner_cands
+        };
+        board = board.make_move(random_point(cands, size, rng)).unwrap();
+    }
+
+    board.winner()
+}
+
+fn backpropagate(tree: &mut [Node], leaf: usize, winner: Option<Side>) {
+    let mut idx = leaf;
+    loop {
+        let parent = tree[idx].parent;
+        tree[idx].num_visits += 1;
+        if let Some(parent) = parent {
+            // The side that moved into a node is the one to move at its parent.
+            let mover = tree[parent].board.turn();
+            tree[idx].reward += match winner {
+                None => 0.5,
+                Some(side) if Some(side) == mover => 1.0,
+                Some(_) => 0.0,
+            };
+            idx = parent;
+        } else {
+            break;
+        }
+    }
+}
+
+fn random_point(cands: BitBoard, size: Size, rng: &mut StdRng) -> Point {
+    let idx = rng.gen_range(0..cands.num_bits()) as usize;
+    cands.points(size).nth(idx).unwrap()
+}
@@ -1,195 +1,309 @@
-pub use self::evaluator::{
-    Evaluate, EvenEvaluator, Score, StrongEvaluator, WeakEvaluator, MAX_SCORE, MIN_SCORE,
+pub use self::{
+    alpha_beta::{MoveOrdering, Player as AlphaBetaPlayer},
+    analysis::{analyze, annotate, Grade, MoveAnalysis},
+    config::{AiConfig, Algorithm, Budget, EvaluatorKind, PlayerKind},
+    evaluator::{
+        Evaluate, EvenEvaluator, PatternEvaluator, PatternWeights, Patterns, Score,
+        StrongEvaluator, WeakEvaluator, MAX_SCORE, MIN_SCORE,
+    },
+    opening::{random_obstacles, random_opening},
+    solver::{solve, Solution},
+    strength::Strength,
 };
-use self::{alpha_beta::Player as AlphaBetaPlayer, random::Player as RandomPlayer};
-use crate::model::{Board, Point, Side};
+use self::{book::Book, mcts::Player as MctsPlayer, random::Player as RandomPlayer};
+use crate::model::{Board, Move, Point, Side};
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
 use std::{
-    sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvError, SendError, Sender, TryRecvError},
+        Arc,
+    },
     thread::{self, JoinHandle},
+    time::Duration,
 };
 
 mod alpha_beta;
+mod analysis;
+mod book;
+mod config;
 mod evaluator;
+mod mcts;
+mod opening;
 mod random;
+mod solver;
+mod strength;
+mod transposition;
 
 #[derive(Clone, Debug)]
 pub enum Message {
-    MakeMove(Side, Point),
+    MakeMove(Side, Move),
     Exit,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub enum PlayerKind {
-    Human,
-    Ai(AiKind),
-}
-
-#[derive(Copy, Clone, Debug)]
-pub enum AiKind {
-    Random,
-    AlphaBetaStrong(AiPower),
-    AlphaBetaEven(AiPower),
-    AlphaBetaWeak(AiPower),
-}
-
-#[derive(Copy, Clone, Debug)]
-pub enum AiPower {
-    Small,
-    Medium,
-    Large,
-}
-
-impl AiPower {
-    fn to_alpha_beta_power(self) -> u32 {
-        use self::AiPower::*;
-        match self {
-            Small => 1_000_000,
-            Medium => 10_000_000,
-            Large => 100_000_000,
-        }
-    }
-}
-
-impl Default for PlayerKind {
-    fn default() -> PlayerKind {
-        PlayerKind::Human
-    }
-}
-
-impl AsRef<str> for PlayerKind {
-    fn as_ref(&self) -> &str {
-        use self::{AiKind::*, AiPower::*, PlayerKind::*};
-        match *self {
-            Human => "Human",
-            Ai(Random) => "AI: random",
-            Ai(AlphaBetaStrong(Small)) => "AI: alpha-beta strong S",
-            Ai(AlphaBetaStrong(Medium)) => "AI: alpha-beta strong M",
-            Ai(AlphaBetaStrong(Large)) => "AI: alpha-beta strong L",
-            Ai(AlphaBetaEven(Small)) => "AI: alpha-beta even S",
-            Ai(AlphaBetaEven(Medium)) => "AI: alpha-beta even M",
-            Ai(AlphaBetaEven(Large)) => "AI: alpha-beta even L",
-            Ai(AlphaBetaWeak(Small)) => "AI: alpha-beta weak S",
-            Ai(AlphaBetaWeak(Medium)) => "AI: alpha-beta weak M",
-            Ai(AlphaBetaWeak(Large)) => "AI: alpha-beta weak L",
-        }
-    }
-}
-
-impl PlayerKind {
-    pub fn all_values() -> [Self; 11] {
-        use self::{AiKind::*, AiPower::*, PlayerKind::*};
-        [
-            Human,
-            Ai(Random),
-            Ai(AlphaBetaStrong(Small)),
-            Ai(AlphaBetaStrong(Medium)),
-            Ai(AlphaBetaStrong(Large)),
-            Ai(AlphaBetaEven(Small)),
-            Ai(AlphaBetaEven(Medium)),
-            Ai(AlphaBetaEven(Large)),
-            Ai(AlphaBetaWeak(Small)),
-            Ai(AlphaBetaWeak(Medium)),
-            Ai(AlphaBetaWeak(Large)),
-        ]
-    }
-
-    pub fn to_index(self) -> usize {
-        use self::{AiKind::*, AiPower::*, PlayerKind::*};
-        match self {
-            Human => 0,
-            Ai(Random) => 1,
-            Ai(AlphaBetaStrong(Small)) => 2,
-            Ai(AlphaBetaStrong(Medium)) => 3,
-            Ai(AlphaBetaStrong(Large)) => 4,
-            Ai(AlphaBetaEven(Small)) => 5,
-            Ai(AlphaBetaEven(Medium)) => 6,
-            Ai(AlphaBetaEven(Large)) => 7,
-            Ai(AlphaBetaWeak(Small)) => 8,
-            Ai(AlphaBetaWeak(Medium)) => 9,
-            Ai(AlphaBetaWeak(Large)) => 10,
-        }
-    }
-}
+// Upper bound on the thinking time of MCTS players with a playout budget,
+// as playouts get slow on large boards.
+const MCTS_TIME_LIMIT: Duration = Duration::from_secs(10);
 
 pub struct AiPlayer {
-    handle: JoinHandle<()>,
-    receiver: Receiver<Point>,
+    handle: JoinHandle<Option<String>>,
+    receiver: Receiver<Move>,
     sender: Sender<Message>,
+    abort: Arc<AtomicBool>,
 }
 
 impl AiPlayer {
-    pub fn try_new(kind: PlayerKind, board: &Board, side: Side) -> Option<AiPlayer> {
-        let ai_kind = match kind {
-            PlayerKind::Human => return None,
-            PlayerKind::Ai(ai_kind) => ai_kind,
+    /// Starts an AI player for `side` unless `kind` is human, or fails if
+    /// its evaluator cannot be loaded. Its random choices are derived from
+    /// `seed` and `side`, which reproduces games unless the search is bounded
+    /// by time or runs on several threads.
+    pub fn try_new(
+        kind: PlayerKind,
+        board: &Board,
+        side: Side,
+        seed: u64,
+    ) -> Result<Option<AiPlayer>, String> {
+        let config = match kind {
+            PlayerKind::Human => return Ok(None),
+            PlayerKind::Ai(config) => config,
+        };
+        let seed = match side {
+            Side::Black => seed,
+            // Keeps the two sides of mirror matches from playing alike.
+            Side::White => StdRng::seed_from_u64(seed).gen(),
         };
+        let abort = Arc::new(AtomicBool::new(false));
+        let mut player = Engine::new(&config, board, side, seed, &abort)?;
 
         let (host_tx, player_rx) = mpsc::channel();
         let (player_tx, host_rx) = mpsc::channel();
         let board = *board;
+        let player_abort = Arc::clone(&abort);
         let handle = thread::spawn(move || {
-            let mut player: Box<dyn FindMove> = match ai_kind {
-                AiKind::Random => Box::new(RandomPlayer::new()),
-                AiKind::AlphaBetaStrong(power) => {
-                    let evaluator = StrongEvaluator::new(board.size());
-                    Box::new(AlphaBetaPlayer::new(
-                        side,
-                        power.to_alpha_beta_power(),
-                        evaluator,
-                    ))
-                }
-                AiKind::AlphaBetaEven(power) => {
-                    let evaluator = EvenEvaluator::new(board.size());
-                    Box::new(AlphaBetaPlayer::new(
-                        side,
-                        power.to_alpha_beta_power(),
-                        evaluator,
-                    ))
-                }
-                AiKind::AlphaBetaWeak(power) => {
-                    let evaluator = WeakEvaluator::new(board.size());
-                    Box::new(AlphaBetaPlayer::new(
-                        side,
-                        power.to_alpha_beta_power(),
-                        evaluator,
-                    ))
-                }
-            };
-            ai_main(side, &player_tx, &player_rx, board, &mut *player);
+            ai_main(
+                side,
+                &player_tx,
+                &player_rx,
+                &player_abort,
+                config.ponder,
+                board,
+                &mut player,
+            )
         });
 
-        Some(AiPlayer {
+        Ok(Some(AiPlayer {
             handle,
             receiver: host_rx,
             sender: host_tx,
-        })
+            abort,
+        }))
     }
 
-    pub fn finish(self) {
+    /// Stops the player, and returns its summary of the game.
+    pub fn finish(self) -> Option<String> {
+        // Interrupts a running search, which would otherwise delay `Exit`.
+        self.abort.store(true, Ordering::Relaxed);
         let _ = self.sender.send(Message::Exit);
-        let _ = self.handle.join();
+        self.handle.join().ok().flatten()
     }
 
-    pub fn listen(&self) -> Result<Point, TryRecvError> {
+    /// The move of the player, passes included, once it has made one.
+    pub fn listen(&self) -> Result<Move, TryRecvError> {
         self.receiver.try_recv()
     }
 
-    pub fn make_move(&self, turn: Side, pt: Point) -> Result<(), SendError<Message>> {
-        self.sender.send(Message::MakeMove(turn, pt))
+    /// Tells the player the move `turn` made.
+    pub fn make_move(&self, turn: Side, mv: Move) -> Result<(), SendError<Message>> {
+        self.sender.send(Message::MakeMove(turn, mv))
     }
 }
 
 pub trait FindMove {
     fn find_move(&mut self, board: Board) -> Point;
+
+    /// Every legal move of `board` with its score, if the player can tell.
+    fn score_moves(&mut self, _board: &Board) -> Option<Vec<(Point, Score)>> {
+        None
+    }
+
+    /// The opponent's most likely move on `board`, if the player has a guess.
+    fn expected_reply(&self, _board: &Board) -> Option<Point> {
+        None
+    }
+
+    /// What the player has to say about the game once it is over.
+    fn summary(&self) -> Option<String> {
+        None
+    }
+
+    /// Finds a move for `board` ahead of time, giving up with `None` once
+    /// `stop` is set.
+    fn ponder(&mut self, _board: Board, _stop: &AtomicBool) -> Option<Point> {
+        None
+    }
+}
+
+// Search player configured by an `AiConfig`, with the opening book and the
+// strength on top.
+struct Engine {
+    player: Box<dyn FindMove + Send>,
+    book: Option<Book>,
+    strength: Strength,
+    rng: StdRng,
+    num_moves: u32,
+    // moves where the strength made the engine deviate from the best move
+    num_weakened: u32,
+}
+
+impl Engine {
+    fn new(
+        config: &AiConfig,
+        board: &Board,
+        side: Side,
+        seed: u64,
+        abort: &Arc<AtomicBool>,
+    ) -> Result<Self, String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let player: Box<dyn FindMove + Send> = match config.algorithm {
+            Algorithm::Random => Box::new(RandomPlayer::new(rng.gen())),
+            Algorithm::AlphaBeta => {
+                let size = board.size();
+                match config.evaluator {
+                    EvaluatorKind::Strong => {
+                        alpha_beta_player(config, side, abort, StrongEvaluator::new(size))
+                    }
+                    EvaluatorKind::Pattern => {
+                        let evaluator = PatternEvaluator::load_default(size)?;
+                        alpha_beta_player(config, side, abort, evaluator)
+                    }
+                    EvaluatorKind::Even => {
+                        alpha_beta_player(config, side, abort, EvenEvaluator::new(size))
+                    }
+                    EvaluatorKind::Weak => {
+                        alpha_beta_player(config, side, abort, WeakEvaluator::new(size))
+                    }
+                }
+            }
+            Algorithm::Mcts => {
+                let player = match config.budget {
+                    Budget::Nodes(num_playouts) => {
+                        MctsPlayer::new(num_playouts).time_limit(MCTS_TIME_LIMIT)
+                    }
+                    Budget::Time(time) => MctsPlayer::new(u32::MAX).time_limit(time),
+                    // MCTS has no notion of depth.
+                    Budget::Depth(_) => MctsPlayer::new(u32::MAX).time_limit(MCTS_TIME_LIMIT),
+                };
+                Box::new(player.seed(rng.gen()).abort_flag(Arc::clone(abort)))
+            }
+        };
+        let book = if config.book {
+            Some(Book::new(board.size()))
+        } else {
+            None
+        };
+        Ok(Engine {
+            player,
+            book,
+            strength: config.strength,
+            rng,
+            num_moves: 0,
+            num_weakened: 0,
+        })
+    }
+
+    fn book_move(&mut self, board: &Board) -> Option<Point> {
+        let rng = &mut self.rng;
+        self.book.as_ref().and_then(|book| book.choose(board, rng))
+    }
+}
+
+impl FindMove for Engine {
+    fn find_move(&mut self, board: Board) -> Point {
+        if let Some(pt) = self.book_move(&board) {
+            return pt;
+        }
+        if self.strength == Strength::Full {
+            return self.player.find_move(board);
+        }
+        let (pt, best) = match self.player.score_moves(&board) {
+            Some(moves) => {
+                let best = moves.iter().max_by_key(|&&(_, score)| score).unwrap().0;
+                (self.strength.choose(&moves, &mut self.rng), best)
+            }
+            None => {
+                let best = self.player.find_move(board);
+                (self.strength.perturb(&board, best, &mut self.rng), best)
+            }
+        };
+        self.num_moves += 1;
+        if pt != best {
+            self.num_weakened += 1;
+        }
+        pt
+    }
+
+    fn summary(&self) -> Option<String> {
+        if self.strength == Strength::Full {
+            return None;
+        }
+        Some(format!(
+            "weakened itself on {} of {} moves",
+            self.num_weakened, self.num_moves
+        ))
+    }
+
+    fn expected_reply(&self, board: &Board) -> Option<Point> {
+        self.player.expected_reply(board)
+    }
+
+    // Pondering only pays off for the best move. Book moves are left to
+    // `find_move`, so that their random choices come in the same order as
+    // without pondering.
+    fn ponder(&mut self, board: Board, stop: &AtomicBool) -> Option<Point> {
+        let in_book = self
+            .book
+            .as_ref()
+            .is_some_and(|book| !book.moves(&board).is_empty());
+        if in_book {
+            return None;
+        }
+        if self.strength == Strength::Full {
+            self.player.ponder(board, stop)
+        } else {
+            None
+        }
+    }
+}
+
+fn alpha_beta_player<E>(
+    config: &AiConfig,
+    side: Side,
+    abort: &Arc<AtomicBool>,
+    evaluator: E,
+) -> Box<dyn FindMove + Send>
+where
+    E: Evaluate + Send + Sync + 'static,
+{
+    Box::new(
+        AlphaBetaPlayer::new(side, 0, evaluator)
+            .budget(config.budget)
+            .num_threads(config.num_threads)
+            .abort_flag(Arc::clone(abort)),
+    )
 }
 
 pub fn ai_main(
     side: Side,
-    tx: &Sender<Point>,
+    tx: &Sender<Move>,
     rx: &Receiver<Message>,
+    abort: &AtomicBool,
+    ponder: bool,
     mut board: Board,
-    player: &mut dyn FindMove,
-) {
+    player: &mut (dyn FindMove + Send),
+) -> Option<String> {
+    // Move found while pondering, for the position after the opponent's
+    // expected reply.
+    let mut pondered = None;
     loop {
         match board.turn() {
             None => match rx.recv() {
@@ -199,9 +313,17 @@ pub fn ai_main(
             },
             Some(turn) => {
                 if turn != side {
-                    match rx.recv() {
-                        Ok(Message::MakeMove(_, pt)) => {
-                            board = board.make_move(pt).expect("cannot make_move");
+                    let (msg, guess) = if ponder {
+                        wait_pondering(side, rx, &board, player)
+                    } else {
+                        (rx.recv(), None)
+                    };
+                    match msg {
+                        Ok(Message::MakeMove(_, mv)) => {
+                            board = board.apply(mv).expect("cannot apply the move");
+                            pondered = guess
+                                .filter(|&(reply, _)| Move::Place(reply) == mv)
+                                .map(|(_, pt)| pt);
                             continue;
                         }
                         Ok(Message::Exit) => break,
@@ -209,10 +331,50 @@ pub fn ai_main(
                     }
                 }
 
-                let pt = player.find_move(board);
-                board = board.make_move(pt).expect("cannot make_move");
-                tx.send(pt).unwrap();
+                let mv = if board.must_pass() {
+                    Move::Pass
+                } else {
+                    match pondered.take() {
+                        Some(pt) => Move::Place(pt),
+                        None => Move::Place(player.find_move(board)),
+                    }
+                };
+                if abort.load(Ordering::Relaxed) {
+                    break;
+                }
+                board = board.apply(mv).expect("cannot apply the move");
+                tx.send(mv).unwrap();
             }
         }
     }
+    player.summary()
+}
+
+// Waits for the opponent's move while searching the position after its
+// expected reply. Returns the message with the reply and the move found for
+// it, if the search completed in time.
+fn wait_pondering(
+    side: Side,
+    rx: &Receiver<Message>,
+    board: &Board,
+    player: &mut (dyn FindMove + Send),
+) -> (Result<Message, RecvError>, Option<(Point, Point)>) {
+    let expected = player.expected_reply(board).and_then(|reply| {
+        let next = board.apply(Move::Place(reply))?;
+        // Nothing to ponder if this player must pass after the reply.
+        (next.turn() == Some(side) && !next.must_pass()).then_some((reply, next))
+    });
+    let (reply, next) = match expected {
+        Some(expected) => expected,
+        None => return (rx.recv(), None),
+    };
+
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let search = scope.spawn(|| player.ponder(next, &stop));
+        let msg = rx.recv();
+        stop.store(true, Ordering::Relaxed);
+        let pt = search.join().unwrap();
+        (msg, pt.map(|pt| (reply, pt)))
+    })
 }
@@ -0,0 +1,106 @@
+use super::{strength, AlphaBetaPlayer, Budget, StrongEvaluator};
+use crate::model::{BitBoard, Board, Move, Point, Symmetry};
+use rand::{seq::IteratorRandom as _, Rng};
+
+// Depth of the search judging how balanced an opening is.
+const BALANCE_DEPTH: u32 = 2;
+// Openings drawn before settling for the most balanced of them.
+const MAX_TRIES: u32 = 100;
+
+/// `plies` random disks placed from `board`, fewer if the game ends, with the
+/// passes before, between and after them. With `balance`, openings are drawn until
+/// a shallow search scores the position within `balance` of even for the
+/// side to move, or the most even one is taken.
+pub fn random_opening<R>(board: &Board, plies: u32, balance: Option<f64>, rng: &mut R) -> Vec<Move>
+where
+    R: Rng,
+{
+    let balance = match balance {
+        Some(balance) => balance,
+        None => return random_moves(board, plies, rng),
+    };
+    let mut most_even = (f64::INFINITY, vec![]);
+    for _ in 0..MAX_TRIES {
+        let moves = random_moves(board, plies, rng);
+        let imbalance = imbalance(&play(board, &moves));
+        if imbalance <= balance {
+            return moves;
+        }
+        if imbalance < most_even.0 {
+            most_even = (imbalance, moves);
+        }
+    }
+    most_even.1
+}
+
+/// About `num` empty cells of `board` to block, in pairs opposite each other
+/// through the centre so that neither side gets the better of them.
+pub fn random_obstacles<R>(board: &Board, num: u32, rng: &mut R) -> Vec<Point>
+where
+    R: Rng,
+{
+    let size = board.size();
+    let mut empty = board.empty_cells();
+    let mut blocked = vec![];
+    while (blocked.len() as u32) < num {
+        let pt = match empty.points(size).choose(rng) {
+            Some(pt) => pt,
+            None => break,
+        };
+        for pt in [pt, Symmetry::Rotate180.apply(pt, size)] {
+            if empty.contains(pt, size) {
+                empty ^= BitBoard::from_point(pt, size);
+                blocked.push(pt);
+            }
+        }
+    }
+    blocked
+}
+
+fn random_moves<R>(board: &Board, plies: u32, rng: &mut R) -> Vec<Move>
+where
+    R: Rng,
+{
+    let size = board.size();
+    let mut board = *board;
+    let mut moves = vec![];
+    let mut num_placed = 0;
+    loop {
+        // Passes are no plies, and the one due after the last ply is made
+        // too, so that the players take over a side with a move.
+        let mv = match board.turn() {
+            Some(_) if board.must_pass() => Move::Pass,
+            Some(_) if num_placed < plies => {
+                num_placed += 1;
+                Move::Place(board.move_candidates().points(size).choose(rng).unwrap())
+            }
+            _ => break,
+        };
+        board = board.apply(mv).unwrap();
+        moves.push(mv);
+    }
+    moves
+}
+
+fn play(board: &Board, moves: &[Move]) -> Board {
+    moves
+        .iter()
+        .fold(*board, |board, &mv| board.apply(mv).unwrap())
+}
+
+// How far from even the position is by a shallow search, whichever side it
+// favours.
+fn imbalance(board: &Board) -> f64 {
+    let side = match board.turn() {
+        Some(side) => side,
+        None => return f64::INFINITY,
+    };
+    let mut player = AlphaBetaPlayer::new(side, 0, StrongEvaluator::new(board.size()))
+        .budget(Budget::Depth(BALANCE_DEPTH));
+    let best = player
+        .score_moves(board)
+        .into_iter()
+        .map(|(_, score)| score)
+        .max();
+    best.map_or(f64::INFINITY, |score| strength::value(score).abs())
+}
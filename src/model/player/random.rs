@@ -1,15 +1,15 @@
 use super::FindMove;
 use crate::model::{Board, Point};
-use rand::{self, rngs::ThreadRng, seq::IteratorRandom as _};
+use rand::{rngs::StdRng, seq::IteratorRandom as _, SeedableRng as _};
 
 pub struct Player {
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         Player {
-            rng: rand::thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 }
@@ -0,0 +1,117 @@
+use crate::model::{format_point, Board, Point, Side};
+use std::fmt;
+
+// Positions with more empty cells than this try the moves leaving the
+// opponent the fewest replies first.
+const MIN_ORDERING_EMPTIES: u32 = 6;
+
+/// Result of a position under perfect play.
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub side: Side,
+    /// Final disk difference for `side`.
+    pub score: i32,
+    /// Every move of `side` reaching `score`.
+    pub best_moves: Vec<Point>,
+    /// A perfect game from the position, passes left out.
+    pub line: Vec<Point>,
+}
+
+// e.g. "Black to move, wins by 4 with d3"
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} to move, ", self.side)?;
+        match self.score {
+            score if score > 0 => write!(f, "wins by {}", score)?,
+            score if score < 0 => write!(f, "loses by {}", -score)?,
+            _ => write!(f, "draws")?,
+        }
+        let moves = self.best_moves.iter().map(|&pt| format_point(pt));
+        write!(f, " with {}", moves.collect::<Vec<_>>().join(" or "))
+    }
+}
+
+/// Solves `board` by exact search, or returns `None` if the game is over.
+/// Only practical with few empty cells.
+pub fn solve(board: &Board) -> Option<Solution> {
+    let side = board.turn()?;
+    let scores = exact_scores(board);
+    let score = scores.iter().map(|&(_, score)| score).max()?;
+    let best_moves = scores
+        .iter()
+        .filter(|&&(_, s)| s == score)
+        .map(|&(pt, _)| pt)
+        .collect::<Vec<_>>();
+
+    let mut line = vec![best_moves[0]];
+    let mut next = board.make_move(best_moves[0]).unwrap();
+    while next.turn().is_some() {
+        let (pt, _) = *exact_scores(&next)
+            .iter()
+            .max_by_key(|&&(_, score)| score)
+            .unwrap();
+        line.push(pt);
+        next = next.make_move(pt).unwrap();
+    }
+    Some(Solution {
+        side,
+        score,
+        best_moves,
+        line,
+    })
+}
+
+// Final disk differences of every move for the side to move.
+fn exact_scores(board: &Board) -> Vec<(Point, i32)> {
+    let side = board.turn().unwrap();
+    let bound = (board.size().0 * board.size().1) as i32;
+    let mut board = *board;
+    let moves = board.move_candidates().points(board.size());
+    moves
+        .map(|pt| {
+            let undo = board.play(pt).unwrap();
+            let score = -negamax(&mut board, side.flip(), -bound, bound);
+            board.undo(undo);
+            (pt, score)
+        })
+        .collect()
+}
+
+// Fail-soft alpha-beta search to the end of the game, making the moves in
+// place. Scores are final disk differences for `side`, which has the turn on
+// `board` unless it must pass.
+fn negamax(board: &mut Board, side: Side, alpha: i32, beta: i32) -> i32 {
+    board.settle();
+    match board.turn() {
+        None => return board.outcome(side),
+        Some(turn) if turn != side => return -negamax(board, turn, -beta, -alpha),
+        Some(_) => {}
+    }
+
+    let size = board.size();
+    let mut moves = board.move_candidates().points(size).collect::<Vec<_>>();
+    if board.num_empty() > MIN_ORDERING_EMPTIES {
+        // The replies are counted without settling the turn, as a pass
+        // leaves none either.
+        moves.sort_by_cached_key(|&pt| {
+            let undo = board.play(pt).unwrap();
+            let num_replies = board.move_candidates().num_bits();
+            board.undo(undo);
+            num_replies
+        });
+    }
+
+    let mut alpha = alpha;
+    let mut best = i32::MIN;
+    for pt in moves {
+        let undo = board.play(pt).unwrap();
+        let score = -negamax(board, side.flip(), -beta, -alpha);
+        board.undo(undo);
+        best = best.max(score);
+        if best >= beta {
+            break;
+        }
+        alpha = alpha.max(best);
+    }
+    best
+}
@@ -0,0 +1,171 @@
+use super::Score;
+use crate::model::{Board, Point};
+use rand::{seq::IteratorRandom as _, Rng};
+use std::{fmt, str::FromStr};
+
+// Value of a won game over the final disk difference, so that softmax
+// probabilities of sure wins and losses dwarf those of running scores.
+const WIN_VALUE: f64 = 100.0;
+
+// Lead beyond which a coaching AI gives away some of its advantage.
+const COACH_MARGIN: f64 = 0.5;
+
+/// How far an AI deviates from the best move it finds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Strength {
+    Full,
+    /// Picks moves with probability proportional to `exp(score / temperature)`.
+    Softmax(f64),
+    /// Plays one of the other moves at random with this probability.
+    ErrorRate(f64),
+    /// Plays the best move while behind, and the move keeping the game
+    /// closest to even while ahead.
+    Coach,
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Strength::Full => write!(f, "full"),
+            Strength::Softmax(temperature) => write!(f, "softmax T={}", temperature),
+            Strength::ErrorRate(rate) => write!(f, "{}% errors", rate * 100.0),
+            Strength::Coach => write!(f, "coach"),
+        }
+    }
+}
+
+// `full`, `softmax:TEMPERATURE` with a positive temperature, `errors:RATE`
+// with a rate from 0 to 1, or `coach`
+impl FromStr for Strength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid strength: {}", s);
+        let mut it = s.splitn(2, ':');
+        let kind = it.next().unwrap();
+        let value = it.next().map(|v| v.parse::<f64>().map_err(|_| invalid()));
+        match (kind, value) {
+            ("full", None) => Ok(Strength::Full),
+            ("coach", None) => Ok(Strength::Coach),
+            ("softmax", Some(temperature)) => match temperature? {
+                t if t.is_finite() && t > 0.0 => Ok(Strength::Softmax(t)),
+                _ => Err(invalid()),
+            },
+            ("errors", Some(rate)) => match rate? {
+                r if (0.0..=1.0).contains(&r) => Ok(Strength::ErrorRate(r)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Strength {
+    /// The strength in the form parsed by `from_str`.
+    pub fn spec(self) -> String {
+        match self {
+            Strength::Full => "full".to_string(),
+            Strength::Softmax(temperature) => format!("softmax:{}", temperature),
+            Strength::ErrorRate(rate) => format!("errors:{}", rate),
+            Strength::Coach => "coach".to_string(),
+        }
+    }
+
+    pub fn choices() -> Vec<Strength> {
+        let mut choices = vec![Strength::Full];
+        choices.extend(
+            [0.05, 0.1, 0.25, 0.5, 1.0, 2.0]
+                .iter()
+                .map(|&t| Strength::Softmax(t)),
+        );
+        choices.extend(
+            [0.05, 0.1, 0.2, 0.5]
+                .iter()
+                .map(|&r| Strength::ErrorRate(r)),
+        );
+        choices.push(Strength::Coach);
+        choices
+    }
+
+    /// Picks one of `moves`, every legal move of a position with its score.
+    pub fn choose<R>(self, moves: &[(Point, Score)], rng: &mut R) -> Point
+    where
+        R: Rng,
+    {
+        let best = moves.iter().max_by_key(|&&(_, score)| score).unwrap().0;
+        match self {
+            Strength::Full => best,
+            Strength::Coach => coach(moves),
+            Strength::Softmax(temperature) => {
+                let max = value(moves.iter().map(|&(_, s)| s).max().unwrap());
+                let weights = moves
+                    .iter()
+                    .map(|&(_, score)| ((value(score) - max) / temperature).exp())
+                    .collect::<Vec<_>>();
+                let mut r = rng.gen::<f64>() * weights.iter().sum::<f64>();
+                for (&(pt, _), w) in moves.iter().zip(&weights) {
+                    if r < *w {
+                        return pt;
+                    }
+                    r -= w;
+                }
+                best
+            }
+            Strength::ErrorRate(rate) => {
+                let others = moves.iter().map(|&(pt, _)| pt).filter(|&pt| pt != best);
+                if rng.gen_bool(rate) {
+                    others.choose(rng).unwrap_or(best)
+                } else {
+                    best
+                }
+            }
+        }
+    }
+
+    /// Like `choose` for players that only tell their best move, which is
+    /// kept when softmax needs the scores of the other moves.
+    pub fn perturb<R>(self, board: &Board, best: Point, rng: &mut R) -> Point
+    where
+        R: Rng,
+    {
+        match self {
+            Strength::Full | Strength::Softmax(_) | Strength::Coach => best,
+            Strength::ErrorRate(rate) => {
+                let size = board.size();
+                let others = board
+                    .move_candidates()
+                    .points(size)
+                    .filter(|&pt| pt != best);
+                if rng.gen_bool(rate) {
+                    others.choose(rng).unwrap_or(best)
+                } else {
+                    best
+                }
+            }
+        }
+    }
+}
+
+fn coach(moves: &[(Point, Score)]) -> Point {
+    let (best, best_score) = *moves.iter().max_by_key(|&&(_, score)| score).unwrap();
+    if value(best_score) <= COACH_MARGIN {
+        return best;
+    }
+    moves
+        .iter()
+        .min_by(|a, b| {
+            let (a, b) = (value(a.1).abs(), value(b.1).abs());
+            a.partial_cmp(&b).unwrap()
+        })
+        .unwrap()
+        .0
+}
+
+pub(super) fn value(score: Score) -> f64 {
+    match score {
+        Score::NegInfinity => f64::NEG_INFINITY,
+        Score::Infinity => f64::INFINITY,
+        Score::Running(v) => v,
+        Score::Ended(v) => f64::from(v.signum()) * WIN_VALUE + f64::from(v),
+    }
+}
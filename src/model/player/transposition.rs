@@ -0,0 +1,126 @@
+use super::Score;
+use crate::model::{Board, Point};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const TABLE_BITS: u32 = 18;
+
+const NO_MOVE: u64 = 0xff;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    pub best_move: Option<Point>,
+    pub score: Score,
+    pub bound: Bound,
+    pub num_eval: f64,
+}
+
+// Searches take the score of an entry only if it was searched with the very
+// same budget, which is rare under node budgets; there the table mainly
+// supplies the best move to try first.
+//
+// Shared between search threads without locking: each slot stores its key
+// xor-ed with the data words, so a slot torn by concurrent writes fails the
+// key check instead of yielding mixed data.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 4]>,
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        TranspositionTable {
+            slots: (0..(1 << TABLE_BITS)).map(|_| Default::default()).collect(),
+        }
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<Entry> {
+        let key = board.hash_key();
+        let slot = &self.slots[index(key)];
+        let words = [
+            slot[0].load(Ordering::Relaxed),
+            slot[1].load(Ordering::Relaxed),
+            slot[2].load(Ordering::Relaxed),
+            slot[3].load(Ordering::Relaxed),
+        ];
+        if words[0] ^ words[1] ^ words[2] ^ words[3] != key {
+            return None;
+        }
+        decode(board, words[1], words[2], words[3])
+    }
+
+    pub fn best_move(&self, board: &Board) -> Option<Point> {
+        self.probe(board).and_then(|entry| entry.best_move)
+    }
+
+    pub fn store(&self, board: &Board, entry: Entry) {
+        let key = board.hash_key();
+        let slot = &self.slots[index(key)];
+        let [score, num_eval, meta] = encode(board, entry);
+        slot[1].store(score, Ordering::Relaxed);
+        slot[2].store(num_eval, Ordering::Relaxed);
+        slot[3].store(meta, Ordering::Relaxed);
+        slot[0].store(key ^ score ^ num_eval ^ meta, Ordering::Relaxed);
+    }
+}
+
+fn index(key: u64) -> usize {
+    (key >> (64 - TABLE_BITS)) as usize
+}
+
+// meta word: bits 0-2 score kind, bits 3-4 bound, bits 8-15 best move offset
+fn encode(board: &Board, entry: Entry) -> [u64; 3] {
+    let (kind, score) = match entry.score {
+        Score::Running(v) => (1, v.to_bits()),
+        Score::Ended(v) => (2, u64::from(v as u32)),
+        Score::NegInfinity => (3, 0),
+        Score::Infinity => (4, 0),
+    };
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let best_move = entry
+        .best_move
+        .map(|pt| u64::from(pt.offset(board.size())))
+        .unwrap_or(NO_MOVE);
+    let meta = kind | bound << 3 | best_move << 8;
+    [score, entry.num_eval.to_bits(), meta]
+}
+
+fn decode(board: &Board, score: u64, num_eval: u64, meta: u64) -> Option<Entry> {
+    let score = match meta & 0x7 {
+        1 => Score::Running(f64::from_bits(score)),
+        2 => Score::Ended(score as u32 as i32),
+        3 => Score::NegInfinity,
+        4 => Score::Infinity,
+        _ => return None,
+    };
+    let bound = match (meta >> 3) & 0x3 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => return None,
+    };
+    let size = board.size();
+    // Hash collisions may yield a move that is illegal in this position.
+    let best_move = match (meta >> 8) & 0xff {
+        NO_MOVE => None,
+        off if off >= u64::from(size.0 * size.1) => None,
+        off => Some(Point::from_offset(off as u32, size))
+            .filter(|&pt| board.move_candidates().contains(pt, size)),
+    };
+    Some(Entry {
+        best_move,
+        score,
+        bound,
+        num_eval: f64::from_bits(num_eval),
+    })
+}
@@ -0,0 +1,74 @@
+use super::{BitBoard, Board, Point, Side, Size, Topology, MAX_SIZE, MIN_SIZE};
+
+/// A position in text form: the rows from the top separated by `/`, with `X`
+/// for black disks, `O` for white disks, `#` for blocked cells and `-` for
+/// empty cells, then the side to move, e.g. `#---/-XO-/-OX-/---# X`, and
+/// `torus` for boards whose edges wrap around.
+pub fn format_position(board: &Board) -> String {
+    let size = board.size();
+    let blocked = board.blocked_cells();
+    let rows = (0..size.1)
+        .map(|y| {
+            (0..size.0)
+                .map(|x| match board.get(Point(x, y)) {
+                    Some(Side::Black) => 'X',
+                    Some(Side::White) => 'O',
+                    None if blocked.contains(Point(x, y), size) => '#',
+                    None => '-',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    let turn = match board.turn() {
+        Some(Side::Black) => "X",
+        Some(Side::White) => "O",
+        None => "-",
+    };
+    match board.topology() {
+        Topology::Planar => format!("{} {}", rows.join("/"), turn),
+        Topology::Torus => format!("{} {} torus", rows.join("/"), turn),
+    }
+}
+
+pub fn parse_position(s: &str) -> Result<Board, String> {
+    let invalid = |reason| format!("invalid position ({}): {}", reason, s);
+    let (cells, turn) = s.trim().split_once(' ').ok_or_else(|| invalid("no side"))?;
+    let (turn, topology) = match turn.trim().split_once(' ') {
+        Some((turn, "torus")) => (turn, Topology::Torus),
+        Some(_) => return Err(invalid("unknown topology")),
+        None => (turn.trim(), Topology::Planar),
+    };
+    let turn = match turn {
+        "X" => Side::Black,
+        "O" => Side::White,
+        _ => return Err(invalid("side must be X or O")),
+    };
+
+    let rows = cells.split('/').collect::<Vec<_>>();
+    let size = Size(rows[0].len() as u32, rows.len() as u32);
+    let valid = MIN_SIZE..=MAX_SIZE;
+    if !valid.contains(&size.0) || !valid.contains(&size.1) {
+        return Err(invalid("bad size"));
+    }
+    let mut black = BitBoard::empty();
+    let mut white = BitBoard::empty();
+    let mut blocked = BitBoard::empty();
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() as u32 != size.0 {
+            return Err(invalid("rows of different lengths"));
+        }
+        for (x, c) in row.chars().enumerate() {
+            let cell = BitBoard::from_point(Point(x as u32, y as u32), size);
+            match c {
+                'X' => black |= cell,
+                'O' => white |= cell,
+                '#' => blocked |= cell,
+                '-' => {}
+                _ => return Err(invalid("unknown cell")),
+            }
+        }
+    }
+    Ok(Board::from_cells(size, black, white, turn)
+        .with_topology(topology)
+        .with_blocked(blocked))
+}
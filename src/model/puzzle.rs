@@ -0,0 +1,36 @@
+use super::{position::parse_position, Board};
+
+/// A position where the side to move has to find the best move.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub board: Board,
+    pub comment: String,
+}
+
+/// Parses one puzzle per line, a position optionally followed by `;` and a
+/// comment. Empty lines and lines starting with `#` are skipped.
+pub fn parse_puzzles(text: &str) -> Result<Vec<Puzzle>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (pos, comment) = line.split_once(';').unwrap_or((line, ""));
+            let board = parse_position(pos)?;
+            if board.turn().is_none() {
+                return Err(format!("game already over: {}", line));
+            }
+            if board.must_pass() {
+                return Err(format!("no move for the side to move: {}", line));
+            }
+            Ok(Puzzle {
+                board,
+                comment: comment.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Endgame problems picked from self-play games by `gen-puzzles`.
+pub fn builtin_puzzles() -> Vec<Puzzle> {
+    parse_puzzles(include_str!("puzzles.txt")).expect("invalid built-in puzzles")
+}
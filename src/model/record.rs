@@ -0,0 +1,395 @@
+use super::{
+    AiConfig, Algorithm, BitBoard, Board, EvaluatorKind, Move, PlayerKind, Point, Rules, Side,
+    Size, StartPosition, Topology, MAX_SIZE, MIN_SIZE,
+};
+use std::{fmt, str::FromStr};
+
+/// Record of a game, with the seed the AI players derived their random
+/// choices from.
+///
+/// The text form has one `key value` line per field:
+///
+/// ```text
+/// size 8x8
+/// start crossed
+/// first black
+/// rules standard
+/// topology planar
+/// blocked a1 h8
+/// seed 42
+/// black level-1
+/// white human
+/// opening 0
+/// moves f5 d6 c3 pass e3
+/// ```
+///
+/// Players are `human`, a preset slug, or every AI setting as `key=value`
+/// pairs separated by spaces. Lines starting with `#` are comments, and
+/// records without `start`, `first`, `rules` or `topology` begin from the
+/// standard start under the standard rules on a board with edges. `blocked`
+/// lists the cells that never hold a disk, and is left out when there are
+/// none. Records without `pass` moves get them back where a side had no
+/// move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub size: Size,
+    pub start: StartPosition,
+    /// The side moving first.
+    pub first: Side,
+    pub rules: Rules,
+    pub topology: Topology,
+    pub blocked: Vec<Point>,
+    pub seed: u64,
+    pub black: PlayerKind,
+    pub white: PlayerKind,
+    /// Number of leading moves made at random rather than by the players.
+    pub opening: usize,
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    pub fn new(size: Size, seed: u64, black: PlayerKind, white: PlayerKind) -> Self {
+        GameRecord {
+            size,
+            start: StartPosition::Crossed,
+            first: Side::Black,
+            rules: Rules::Standard,
+            topology: Topology::Planar,
+            blocked: vec![],
+            seed,
+            black,
+            white,
+            opening: 0,
+            moves: vec![],
+        }
+    }
+
+    /// The position before the first move.
+    pub fn initial_board(&self) -> Board {
+        let blocked = self.blocked.iter().fold(BitBoard::empty(), |cells, &pt| {
+            cells | BitBoard::from_point(pt, self.size)
+        });
+        Board::with_start(self.size, self.start, self.first)
+            .with_rules(self.rules)
+            .with_topology(self.topology)
+            .with_blocked(blocked)
+    }
+
+    /// The position after the opening moves.
+    pub fn start_board(&self) -> Option<Board> {
+        self.moves[..self.opening]
+            .iter()
+            .try_fold(self.initial_board(), |board, &mv| board.apply(mv))
+    }
+
+    // Puts back the passes of records written before passes were recorded,
+    // counting those right after the opening moves in the opening. Moves
+    // after an illegal one are kept as they are.
+    fn insert_passes(&mut self) {
+        let mut board = self.initial_board();
+        let mut moves = vec![];
+        let mut opening = self.opening;
+        for (i, &mv) in self.moves.iter().enumerate() {
+            if mv != Move::Pass && board.must_pass() {
+                moves.push(Move::Pass);
+                board = board.apply(Move::Pass).unwrap();
+                if i <= self.opening {
+                    opening += 1;
+                }
+            }
+            moves.push(mv);
+            board = match board.apply(mv) {
+                Some(board) => board,
+                None => {
+                    moves.extend_from_slice(&self.moves[i + 1..]);
+                    break;
+                }
+            };
+        }
+        self.moves = moves;
+        self.opening = opening;
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "size {}x{}", self.size.0, self.size.1)?;
+        writeln!(f, "start {}", self.start.as_ref())?;
+        writeln!(f, "first {}", self.first.as_ref())?;
+        writeln!(f, "rules {}", self.rules.as_ref())?;
+        writeln!(f, "topology {}", self.topology.as_ref())?;
+        if !self.blocked.is_empty() {
+            let blocked = self.blocked.iter().map(|&pt| format_point(pt));
+            writeln!(f, "blocked {}", blocked.collect::<Vec<_>>().join(" "))?;
+        }
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "black {}", format_player(self.black))?;
+        writeln!(f, "white {}", format_player(self.white))?;
+        writeln!(f, "opening {}", self.opening)?;
+        let moves = self.moves.iter().map(|&mv| format_move(mv));
+        writeln!(f, "moves {}", moves.collect::<Vec<_>>().join(" "))
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new(Size(8, 8), 0, PlayerKind::Human, PlayerKind::Human);
+        let lines = s.lines().map(str::trim);
+        for line in lines.filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let invalid = || format!("invalid {}: {}", key, value);
+            match key {
+                "size" => {
+                    let (cols, rows) = value.split_once('x').ok_or_else(invalid)?;
+                    let cols = cols.parse().map_err(|_| invalid())?;
+                    let rows = rows.parse().map_err(|_| invalid())?;
+                    record.size = Size(cols, rows);
+                }
+                "start" => {
+                    record.start = *StartPosition::all_values()
+                        .iter()
+                        .find(|s| s.as_ref() == value)
+                        .ok_or_else(invalid)?;
+                }
+                "first" => {
+                    record.first = *Side::all_values()
+                        .iter()
+                        .find(|s| s.as_ref() == value)
+                        .ok_or_else(invalid)?;
+                }
+                "rules" => {
+                    record.rules = *Rules::all_values()
+                        .iter()
+                        .find(|r| r.as_ref() == value)
+                        .ok_or_else(invalid)?;
+                }
+                "topology" => {
+                    record.topology = *Topology::all_values()
+                        .iter()
+                        .find(|t| t.as_ref() == value)
+                        .ok_or_else(invalid)?;
+                }
+                "blocked" => {
+                    record.blocked = value
+                        .split_whitespace()
+                        .map(|pt| parse_point(pt).ok_or_else(|| format!("invalid cell: {}", pt)))
+                        .collect::<Result<_, _>>()?;
+                }
+                "seed" => record.seed = value.parse().map_err(|_| invalid())?,
+                "black" => record.black = parse_player(value)?,
+                "white" => record.white = parse_player(value)?,
+                "opening" => record.opening = value.parse().map_err(|_| invalid())?,
+                "moves" => {
+                    record.moves = value
+                        .split_whitespace()
+                        .map(|mv| parse_move(mv).ok_or_else(|| format!("invalid move: {}", mv)))
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(format!("unknown record line: {}", line)),
+            }
+        }
+        let Size(cols, rows) = record.size;
+        let valid = MIN_SIZE..=MAX_SIZE;
+        if !valid.contains(&cols) || !valid.contains(&rows) {
+            return Err(format!("invalid size: {}x{}", cols, rows));
+        }
+        // The four disks of the start take the centre.
+        let is_centre = |Point(x, y): Point| {
+            x + 1 >= cols / 2 && x <= cols / 2 && y + 1 >= rows / 2 && y <= rows / 2
+        };
+        if let Some(&pt) = record
+            .blocked
+            .iter()
+            .find(|&&pt| pt.0 >= cols || pt.1 >= rows || is_centre(pt))
+        {
+            return Err(format!("cell cannot be blocked: {}", format_point(pt)));
+        }
+        if let Some(&mv) = record.moves.iter().find(|&&mv| match mv {
+            Move::Place(pt) => pt.0 >= cols || pt.1 >= rows,
+            Move::Pass => false,
+        }) {
+            return Err(format!("invalid move: {}", format_move(mv)));
+        }
+        if record.opening > record.moves.len() {
+            return Err(format!("opening longer than the game: {}", record.opening));
+        }
+        record.insert_passes();
+        Ok(record)
+    }
+}
+
+/// A point in standard notation, columns `a`, `b`, ... and rows from 1.
+pub fn format_point(Point(x, y): Point) -> String {
+    format!("{}{}", char::from(b'a' + x as u8), y + 1)
+}
+
+pub fn parse_point(s: &str) -> Option<Point> {
+    let mut chars = s.chars();
+    let col = chars.next().filter(char::is_ascii_lowercase)?;
+    let row = chars.as_str().parse::<u32>().ok().filter(|&row| row > 0)?;
+    Some(Point(u32::from(col) - u32::from('a'), row - 1))
+}
+
+/// A point in standard notation, or `pass`.
+pub fn format_move(mv: Move) -> String {
+    match mv {
+        Move::Place(pt) => format_point(pt),
+        Move::Pass => "pass".to_string(),
+    }
+}
+
+pub fn parse_move(s: &str) -> Option<Move> {
+    match s {
+        "pass" => Some(Move::Pass),
+        _ => parse_point(s).map(Move::Place),
+    }
+}
+
+fn format_player(kind: PlayerKind) -> String {
+    let config = match kind {
+        PlayerKind::Human => return "human".to_string(),
+        PlayerKind::Ai(config) => config,
+    };
+    if let Some(preset) = AiConfig::presets()
+        .into_iter()
+        .find(|preset| preset.config == config)
+    {
+        return preset.slug();
+    }
+    format!(
+        "algorithm={} evaluator={} budget={} strength={} book={} threads={} ponder={}",
+        config.algorithm.as_ref(),
+        config.evaluator.as_ref(),
+        config.budget.spec(),
+        config.strength.spec(),
+        config.book,
+        config.num_threads,
+        config.ponder
+    )
+}
+
+fn parse_player(s: &str) -> Result<PlayerKind, String> {
+    if s == "human" {
+        return Ok(PlayerKind::Human);
+    }
+    if let Some(config) = AiConfig::from_slug(s) {
+        return Ok(PlayerKind::Ai(config));
+    }
+    if !s.contains('=') {
+        return Err(format!("unknown player: {}", s));
+    }
+
+    let mut config = AiConfig::presets()[0].config;
+    for setting in s.split_whitespace() {
+        let invalid = || format!("invalid player setting: {}", setting);
+        let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+        match key {
+            "algorithm" => {
+                config.algorithm = *Algorithm::all_values()
+                    .iter()
+                    .find(|a| a.as_ref() == value)
+                    .ok_or_else(invalid)?;
+            }
+            "evaluator" => {
+                config.evaluator = *EvaluatorKind::all_values()
+                    .iter()
+                    .find(|e| e.as_ref() == value)
+                    .ok_or_else(invalid)?;
+            }
+            "budget" => config.budget = value.parse()?,
+            "strength" => config.strength = value.parse()?,
+            "book" => config.book = value.parse().map_err(|_| invalid())?,
+            "threads" => {
+                config.num_threads = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(invalid()),
+                }
+            }
+            "ponder" => config.ponder = value.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(PlayerKind::Ai(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{player::Budget, random_opening, Strength};
+    use rand::{rngs::StdRng, SeedableRng as _};
+    use std::time::Duration;
+
+    fn parse(s: &str) -> Result<GameRecord, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn round_trip() {
+        let custom = AiConfig {
+            budget: Budget::Time(Duration::from_millis(1500)),
+            strength: Strength::Softmax(0.25),
+            num_threads: 3,
+            ponder: true,
+            ..AiConfig::presets()[1].config
+        };
+        let players = [
+            (
+                PlayerKind::Human,
+                PlayerKind::Ai(AiConfig::presets()[2].config),
+            ),
+            (PlayerKind::Ai(custom), PlayerKind::Human),
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        for &size in &[Size(4, 4), Size(8, 8), Size(5, 7)] {
+            for (i, &(black, white)) in players.iter().enumerate() {
+                let mut record = GameRecord::new(size, 42, black, white);
+                if i == 1 {
+                    record.start = StartPosition::Parallel;
+                    record.first = Side::White;
+                    record.rules = Rules::Misere;
+                    record.topology = Topology::Torus;
+                    record.blocked = vec![Point(0, 0), Point(size.0 - 1, size.1 - 1)];
+                }
+                // A whole game, passes included.
+                record.moves = random_opening(&record.initial_board(), 100, None, &mut rng);
+                record.opening = 3;
+                assert_eq!(parse(&record.to_string()), Ok(record.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn legacy_passes_come_back() {
+        // Black has no move at the start, and none after a3.
+        let legacy = "size 4x4\nblocked b1 a2 d3 c4\nopening 3\nmoves b4 a4 a3 d2 d1 c1\n";
+        let record = parse(legacy).unwrap();
+        let moves = record.moves.iter().map(|&mv| format_move(mv));
+        assert_eq!(
+            moves.collect::<Vec<_>>().join(" "),
+            "pass b4 a4 a3 pass d2 d1 c1"
+        );
+        // Both passes came before the fourth move, the first of the players.
+        assert_eq!(record.opening, 5);
+        assert_eq!(parse(&record.to_string()), Ok(record));
+    }
+
+    #[test]
+    fn invalid_records() {
+        let err = |s: &str| parse(s).unwrap_err();
+        assert_eq!(err("size 4x4\nmoves z9\n"), "invalid move: z9");
+        assert_eq!(err("size 4x4\nmoves b5\n"), "invalid move: b5");
+        assert_eq!(err("size 4x4\nblocked b2\n"), "cell cannot be blocked: b2");
+        assert_eq!(err("size 4x4\nblocked e1\n"), "cell cannot be blocked: e1");
+        assert_eq!(
+            err("size 4x4\nopening 2\nmoves b3\n"),
+            "opening longer than the game: 2"
+        );
+        assert_eq!(err("size 11x8\n"), "invalid size: 11x8");
+        assert_eq!(
+            err("black algorithm=alpha-beta threads=0\n"),
+            "invalid player setting: threads=0"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Point, Size};
+
+/// Rotations and reflections of a board. Rotating by 90 degrees and the
+/// transpositions swap the width and the height.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Symmetry {
+    Identity,
+    /// Clockwise by 90 degrees.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors left and right.
+    FlipX,
+    /// Mirrors top and bottom.
+    FlipY,
+    /// Mirrors along the diagonal through the top left corner.
+    Transpose,
+    /// Mirrors along the diagonal through the top right corner.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub fn all_values() -> [Self; 8] {
+        use self::Symmetry::*;
+        [
+            Identity,
+            Rotate90,
+            Rotate180,
+            Rotate270,
+            FlipX,
+            FlipY,
+            Transpose,
+            AntiTranspose,
+        ]
+    }
+
+    /// The symmetries mapping boards of `size` onto boards of the same
+    /// size: all eight for square boards, four for rectangular ones.
+    pub fn of_size(size: Size) -> &'static [Self] {
+        use self::Symmetry::*;
+        if size.0 == size.1 {
+            &[
+                Identity,
+                Rotate90,
+                Rotate180,
+                Rotate270,
+                FlipX,
+                FlipY,
+                Transpose,
+                AntiTranspose,
+            ]
+        } else {
+            &[Identity, Rotate180, FlipX, FlipY]
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            sym => sym,
+        }
+    }
+
+    /// Whether the width and the height trade places.
+    pub fn swaps_axes(self) -> bool {
+        use self::Symmetry::*;
+        match self {
+            Rotate90 | Rotate270 | Transpose | AntiTranspose => true,
+            Identity | Rotate180 | FlipX | FlipY => false,
+        }
+    }
+
+    /// The size of boards of `size` once transformed.
+    pub fn apply_size(self, size: Size) -> Size {
+        if self.swaps_axes() {
+            Size(size.1, size.0)
+        } else {
+            size
+        }
+    }
+
+    /// Where `pt` of a board of `size` goes.
+    pub fn apply(self, Point(x, y): Point, size: Size) -> Point {
+        use self::Symmetry::*;
+        let Size(w, h) = size;
+        match self {
+            Identity => Point(x, y),
+            Rotate90 => Point(h - 1 - y, x),
+            Rotate180 => Point(w - 1 - x, h - 1 - y),
+            Rotate270 => Point(y, w - 1 - x),
+            FlipX => Point(w - 1 - x, y),
+            FlipY => Point(x, h - 1 - y),
+            Transpose => Point(y, x),
+            AntiTranspose => Point(h - 1 - y, w - 1 - x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{model::Board, tool::random_positions};
+
+    const SIZES: [Size; 5] = [Size(4, 4), Size(6, 6), Size(8, 8), Size(5, 7), Size(10, 6)];
+
+    #[test]
+    fn inverse_undoes_transform() {
+        for &size in &SIZES {
+            for board in random_positions(&Board::new(size), 1) {
+                for &sym in &Symmetry::all_values() {
+                    let transformed = board.transform(sym);
+                    assert_eq!(transformed.size(), sym.apply_size(size));
+                    let back = transformed.transform(sym.inverse());
+                    assert_eq!(back, board, "{:?}", sym);
+                    assert_eq!(back.hash_key(), board.hash_key(), "{:?}", sym);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn images_share_canonical_form() {
+        for &size in &SIZES {
+            for board in random_positions(&Board::new(size), 1) {
+                let (canonical, to_canonical) = board.canonical();
+                assert_eq!(board.transform(to_canonical), canonical);
+                for &sym in Symmetry::of_size(size) {
+                    let image = board.transform(sym);
+                    assert_eq!(image.canonical().0, canonical, "{:?}", sym);
+                    assert_eq!(image.canonical_hash_key(), board.canonical_hash_key());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rectangles_keep_their_shape() {
+        for &size in &SIZES {
+            let syms = Symmetry::of_size(size);
+            let expected = if size.0 == size.1 { 8 } else { 4 };
+            assert_eq!(syms.len(), expected);
+            assert!(syms.iter().all(|sym| sym.apply_size(size) == size));
+            // The others turn it on its side.
+            for sym in Symmetry::all_values() {
+                if !syms.contains(&sym) {
+                    assert_ne!(sym.apply_size(size), size);
+                }
+            }
+        }
+    }
+}
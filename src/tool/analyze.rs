@@ -0,0 +1,48 @@
+use super::Options;
+use crate::model::{self, GameRecord, Grade, Side};
+use std::fs;
+
+const OPTIONS: &[&str] = &["depth", "exact-empties", "output"];
+
+// Annotates the moves of a game record with the best alternatives and the
+// score losses.
+pub fn analyze(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or("analyze needs a record file")?;
+    let opts = Options::parse(&args[1..], OPTIONS)?;
+    let depth = opts.get("depth", 6)?;
+    let exact_empties = opts.get("exact-empties", 10)?;
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let record = text.parse::<GameRecord>()?;
+
+    let mut board = record.initial_board();
+    for (i, &mv) in record.moves.iter().enumerate() {
+        board = board
+            .apply(mv)
+            .ok_or_else(|| format!("illegal move {}: {}", i + 1, model::format_move(mv)))?;
+    }
+
+    let analyses = model::analyze(&record, depth, exact_empties).collect::<Vec<_>>();
+    let annotated = model::annotate(&record, &analyses);
+    print!("{}", annotated);
+    for &(grade, label) in &[
+        (Grade::Inaccuracy, "inaccuracies"),
+        (Grade::Blunder, "blunders"),
+    ] {
+        let count = |side| {
+            analyses
+                .iter()
+                .filter(|a| a.side == side && a.grade == grade)
+                .count()
+        };
+        println!(
+            "{}: black {}, white {}",
+            label,
+            count(Side::Black),
+            count(Side::White)
+        );
+    }
+    if let Some(output) = opts.get_str("output") {
+        fs::write(output, annotated).map_err(|e| format!("{}: {}", output, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use super::{parse_arg, perft, random_positions};
+use crate::model::{
+    AlphaBetaPlayer, BitBoard, Board, FindMove, MoveOrdering, Point, Side, Size, StrongEvaluator,
+    MAX_SIZE,
+};
+use std::time::Instant;
+
+const POSITIONS_SEED: u64 = 0x0123_4567_89ab_cdef;
+
+pub fn ordering(args: &[String]) -> Result<(), String> {
+    let num_eval = parse_arg(args, 0, 1_000_000)?;
+
+    println!("transposition table entries order the moves but seldom cut off under node budgets");
+    println!(
+        "{:>4} {:>5} {:>12} {:>12} {:>7}",
+        "#", "disks", "natural", "heuristic", "ratio"
+    );
+    let mut total = (0, 0);
+    for (i, board) in random_positions(&Board::new(Size(8, 8)), POSITIONS_SEED)
+        .iter()
+        .enumerate()
+    {
+        let natural = count_nodes(board, num_eval, MoveOrdering::Natural);
+        let heuristic = count_nodes(board, num_eval, MoveOrdering::Heuristic);
+        let disks = board.num_disk(Side::Black) + board.num_disk(Side::White);
+        println!(
+            "{:>4} {:>5} {:>12} {:>12} {:>7.3}",
+            i,
+            disks,
+            natural,
+            heuristic,
+            heuristic as f64 / natural as f64
+        );
+        total.0 += natural;
+        total.1 += heuristic;
+    }
+    println!(
+        "{:>4} {:>5} {:>12} {:>12} {:>7.3}",
+        "all",
+        "",
+        total.0,
+        total.1,
+        total.1 as f64 / total.0 as f64
+    );
+
+    Ok(())
+}
+
+fn count_nodes(board: &Board, num_eval: u32, ordering: MoveOrdering) -> u64 {
+    let side = board.turn().unwrap();
+    let evaluator = StrongEvaluator::new(board.size());
+    let mut player = AlphaBetaPlayer::with_ordering(side, num_eval, evaluator, ordering);
+    let _ = player.find_move(*board);
+    player.num_nodes()
+}
+
+// Measures the speed of move generation alone by perft, against the cell by
+// cell generator it replaced, and within alpha-beta searches of the
+// benchmark positions.
+pub fn movegen(args: &[String]) -> Result<(), String> {
+    let depth = parse_arg(args, 0, 9)?;
+    let num_eval = parse_arg(args, 1, 1_000_000)?;
+
+    let start = Instant::now();
+    let baseline = CellByCell::new().count(&Board::new(Size(8, 8)), depth);
+    let baseline_speed = baseline as f64 / start.elapsed().as_secs_f64();
+    println!(
+        "perft {} cell by cell: {} leaves, {:.0} leaves/s",
+        depth, baseline, baseline_speed
+    );
+
+    for in_place in [false, true] {
+        let start = Instant::now();
+        let (name, leaves) = if in_place {
+            let board = &mut Board::new(Size(8, 8));
+            (" in place", perft::count_in_place(board, depth))
+        } else {
+            ("", perft::count(&Board::new(Size(8, 8)), depth))
+        };
+        let speed = leaves as f64 / start.elapsed().as_secs_f64();
+        if leaves != baseline {
+            return Err(format!(
+                "perft {}{}: {} leaves, cell by cell {}",
+                depth, name, leaves, baseline
+            ));
+        }
+        println!(
+            "perft {}{}: {} leaves, {:.0} leaves/s, {:.2}x cell by cell",
+            depth,
+            name,
+            leaves,
+            speed,
+            speed / baseline_speed
+        );
+    }
+
+    let mut num_nodes = 0;
+    let start = Instant::now();
+    for board in &random_positions(&Board::new(Size(8, 8)), POSITIONS_SEED) {
+        num_nodes += count_nodes(board, num_eval, MoveOrdering::Heuristic);
+    }
+    let secs = start.elapsed().as_secs_f64();
+    println!(
+        "alpha-beta: {} nodes in {:.3}s, {:.0} nodes/s",
+        num_nodes,
+        secs,
+        num_nodes as f64 / secs
+    );
+    Ok(())
+}
+
+// The move generator of 8x8 boards before the fills: runs of disks followed
+// one cell at a time along each direction, as far as the largest board.
+struct CellByCell {
+    // per direction, the cells with a neighbour along it and the offset to
+    // that neighbour
+    steps: [(BitBoard, i32); 8],
+}
+
+impl CellByCell {
+    const SIZE: Size = Size(8, 8);
+
+    fn new() -> Self {
+        let dirs = [
+            (1, 0),
+            (0, 1),
+            (-1, 1),
+            (1, 1),
+            (-1, 0),
+            (0, -1),
+            (1, -1),
+            (-1, -1),
+        ];
+        let steps = dirs.map(|(dx, dy): (i32, i32)| {
+            let inside = |x: i32, y: i32| (0..8).contains(&x) && (0..8).contains(&y);
+            let cells = perft::points(Self::SIZE)
+                .filter(|&Point(x, y)| inside(x as i32 + dx, y as i32 + dy))
+                .fold(BitBoard::empty(), |cells, pt| {
+                    cells | BitBoard::from_point(pt, Self::SIZE)
+                });
+            (cells, dy * 8 + dx)
+        });
+        CellByCell { steps }
+    }
+
+    fn step(&self, dir: usize, bits: BitBoard) -> BitBoard {
+        let (cells, off) = self.steps[dir];
+        if off > 0 {
+            (bits & cells) << off as u32
+        } else {
+            (bits & cells) >> -off as u32
+        }
+    }
+
+    fn moves(&self, me: BitBoard, you: BitBoard) -> BitBoard {
+        let empty = BitBoard::all_filled(Self::SIZE) & !me & !you;
+        let mut moves = BitBoard::empty();
+        for dir in 0..8 {
+            let mut run = self.step(dir, me) & you;
+            for _ in 0..MAX_SIZE - 2 {
+                run |= self.step(dir, run) & you;
+            }
+            moves |= self.step(dir, run) & empty;
+        }
+        moves
+    }
+
+    fn flips(&self, me: BitBoard, you: BitBoard, placed: BitBoard) -> BitBoard {
+        let mut flips = BitBoard::empty();
+        for dir in 0..8 {
+            let mut run = BitBoard::empty();
+            let mut cur = self.step(dir, placed);
+            while !(cur & you).is_empty() {
+                run |= cur;
+                cur = self.step(dir, cur);
+            }
+            if !(cur & me).is_empty() {
+                flips |= run;
+            }
+        }
+        flips
+    }
+
+    // Same as `perft::count` from `board`.
+    fn count(&self, board: &Board, depth: u32) -> u64 {
+        let turn = board.turn().unwrap();
+        self.count_cells(board.cells(turn), board.cells(turn.flip()), depth)
+    }
+
+    fn count_cells(&self, me: BitBoard, you: BitBoard, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.moves(me, you);
+        if moves.is_empty() {
+            if self.moves(you, me).is_empty() {
+                return 1;
+            }
+            return self.count_cells(you, me, depth - 1);
+        }
+        moves
+            .points(Self::SIZE)
+            .map(|pt| {
+                let placed = BitBoard::from_point(pt, Self::SIZE);
+                let flips = self.flips(me, you, placed);
+                self.count_cells(you & !flips, me | flips | placed, depth - 1)
+            })
+            .sum()
+    }
+}
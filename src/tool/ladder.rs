@@ -0,0 +1,81 @@
+use super::{parse_size, Opening, Options};
+use crate::model::{
+    AiConfig, Algorithm, AlphaBetaPlayer, FindMove, GameRecord, PlayerKind, Side, Size, Strength,
+    StrongEvaluator,
+};
+use rand::{rngs::StdRng, SeedableRng as _};
+
+const OPTIONS: &[&str] = &["games", "size", "seed"];
+
+pub fn ladder(args: &[String]) -> Result<(), String> {
+    let opts = Options::parse(args, &[OPTIONS, Opening::OPTIONS].concat())?;
+    let size = match opts.get_str("size") {
+        Some(s) => parse_size(s)?,
+        None => Size(8, 8),
+    };
+    let num_games = opts.get("games", 20)?;
+    let opening = Opening::parse(&opts, 4)?;
+    let seed = opts.get("seed", 1)?;
+
+    // The presets weakened by softmax, each against its full strength self.
+    let levels = AiConfig::presets()
+        .into_iter()
+        .filter(|preset| {
+            let config = preset.config;
+            config.algorithm == Algorithm::AlphaBeta && config.strength != Strength::Full
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "{:<10} {:<16} {:>5} {:>5} {:>6} {:>9}",
+        "preset", "strength", "wins", "draws", "losses", "mean diff"
+    );
+    for preset in levels {
+        let config = preset.config;
+        // Every level sees the same openings.
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut total = [0, 0, 0];
+        let mut total_diff = 0;
+        for game in 0..num_games {
+            let side = if game % 2 == 0 {
+                Side::Black
+            } else {
+                Side::White
+            };
+            let diff = play(config, side, size, &opening, &mut rng);
+            total[(1 - diff.signum()) as usize] += 1;
+            total_diff += diff;
+        }
+        println!(
+            "{:<10} {:<16} {:>5} {:>5} {:>6} {:>+9.2}",
+            preset.name,
+            config.strength.to_string(),
+            total[0],
+            total[1],
+            total[2],
+            f64::from(total_diff) / f64::from(num_games)
+        );
+    }
+    Ok(())
+}
+
+// Returns the final disk difference, as the rules count it, for the weakened
+// player on `side`.
+fn play(config: AiConfig, side: Side, size: Size, opening: &Opening, rng: &mut StdRng) -> i32 {
+    let player =
+        |side| AlphaBetaPlayer::new(side, 0, StrongEvaluator::new(size)).budget(config.budget);
+    let mut weakened = player(side);
+    let mut full = player(side.flip());
+
+    let mut record = GameRecord::new(size, 0, PlayerKind::Human, PlayerKind::Human);
+    let mut board = opening.play(&mut record, rng);
+    while let Some(turn) = board.turn() {
+        let pt = if turn == side {
+            config.strength.choose(&weakened.score_moves(&board), rng)
+        } else {
+            full.find_move(board)
+        };
+        board = board.make_move(pt).unwrap();
+    }
+    board.outcome(side)
+}
@@ -0,0 +1,307 @@
+use crate::model::{
+    random_obstacles, random_opening, Board, GameRecord, Rules, Side, Size, StartPosition,
+    Topology, MAX_SIZE, MIN_SIZE,
+};
+use rand::{rngs::StdRng, seq::IteratorRandom as _, Rng, SeedableRng as _};
+use std::{collections::HashMap, str::FromStr};
+
+mod analyze;
+mod bench;
+mod ladder;
+mod perft;
+mod puzzle;
+mod replay;
+mod train;
+mod verify;
+mod versus;
+
+#[cfg(test)]
+pub(crate) use self::verify::check as check_search;
+
+const USAGE: &str = "\
+usage: othello [COMMAND [ARGS...]]
+
+Starts the game when no command is given.
+
+commands:
+    analyze FILE [--OPTION VALUE]...
+                                 grade the moves of a game record, flagging blunders
+        --depth N                search depth (default: 6)
+        --exact-empties N        search exactly with at most N empty cells (default: 10)
+        --output PATH            write the record with the analysis as comments
+    bench-movegen [DEPTH [NUM_EVAL]]
+                                 measure move generation speed by perft to DEPTH (default: 9),
+                                 cell by cell as before the fills, copying and in place, and by
+                                 alpha-beta searches of NUM_EVAL nodes (default: 1000000)
+    bench-ordering [NUM_EVAL]    compare alpha-beta node counts with and without move ordering
+    gen-puzzles [--OPTION VALUE]...
+                                 pick endgame puzzles with a single best move from self-play
+        --size COLSxROWS         board size (default: 8x8)
+        --puzzles N              number of puzzles (default: 20)
+        --empties N              empty cells of the puzzle positions (default: 12)
+        --num-eval N             search budget of the self-play players (default: 10000)
+        --random-plies N         random moves at the start of each game (default: 10)
+        --seed N                 random seed (default: 1)
+        --output PATH            puzzle file (default: standard output)
+    ladder [--OPTION VALUE]...   play the weakened presets against their full strength selves
+        --games N                games per preset, alternating sides (default: 20)
+        --size COLSxROWS         board size (default: 8x8)
+        --start crossed|parallel central disks at the start (default: crossed)
+        --first black|white      side moving first (default: black)
+        --rules standard|misere  whether more or fewer disks win (default: standard)
+        --topology planar|torus  whether the edges wrap around (default: planar)
+        --obstacles N            block about N random cells, in pairs opposite through the
+                                 centre (default: 0)
+        --random-plies N         random moves at the start of each game (default: 4)
+        --balance SCORE          redraw the random moves until a shallow search scores the
+                                 position within SCORE of even (default: any moves)
+        --seed N                 random seed of the openings and weakened moves (default: 1)
+    perft [--OPTION VALUE]...    count the positions at each depth of the game tree, passes
+                                 being plies, and check them by a cell by cell move generator
+        --size COLSxROWS         board size (default: every size)
+        --topology planar|torus  whether the edges wrap around (default: planar)
+        --depth N                deepest depth (default: 6)
+        --reference-depth N      deepest depth to check (default: the depth)
+    replay FILE                  replay an AI game record by its seed, checking the moves
+    solve [FILE]                 solve the puzzles of FILE, or the built-in ones, exactly
+    train [--OPTION VALUE]...    fit pattern evaluator weights to self-play games
+        --size COLSxROWS         board size (default: 8x8)
+        --games N                number of self-play games (default: 1000)
+        --num-eval N             search budget of the self-play players (default: 1000)
+        --random-plies N         random moves at the start of each game (default: 8)
+        --epsilon P              probability of a random move afterwards (default: 0.1)
+        --exact-empties N        label positions with at most N empty cells by
+                                 exact search instead of the game result (default: 0)
+        --phases N               number of game phases (default: 6)
+        --epochs N               passes over the positions (default: 20)
+        --rate R                 learning rate (default: 0.1)
+        --seed N                 random seed (default: 1)
+        --output PATH            weight file (default: the one the pattern AI loads)
+    verify-perft [DEPTH [REFERENCE_DEPTH]]
+                                 check move generation by leaf counts of the game tree
+                                 (default: to depth 9, by the reference to depth 7)
+    verify-features              check the stable disks by random play to the end, and the
+                                 other positional features by cell by cell counts
+    verify-search [NUM_EVAL [THREADS]]
+                                 check the alpha-beta search against a plain minimax search
+    verify-symmetry              check board rotations and reflections against move generation
+    versus AI AI [--OPTION VALUE]...
+                                 play AIs against each other, e.g. `versus mcts-s random`
+        --games N                number of games, the AIs swapping sides (default: 10)
+        --size COLSxROWS         board size (default: 8x8)
+        --budget KIND:N          budget of both AIs, `nodes:N`, `time:MILLIS` or `depth:N`
+                                 (default: the presets' budgets)
+        --book true|false        let the AIs use the opening book (default: false)
+        --threads N              search threads of each AI (default: 1)
+        --ponder true|false      let the AIs think on the opponent's time (default: false)
+        --start crossed|parallel central disks at the start (default: crossed)
+        --first black|white      side moving first (default: black)
+        --rules standard|misere  whether more or fewer disks win (default: standard)
+        --topology planar|torus  whether the edges wrap around (default: planar)
+        --obstacles N            block about N random cells, in pairs opposite through the
+                                 centre (default: 0)
+        --random-plies N         random moves at the start of each game (default: 4)
+        --balance SCORE          redraw the random moves until a shallow search scores the
+                                 position within SCORE of even (default: any moves)
+        --seed N                 random seed of the openings and the AIs (default: 1)
+        --records DIR            write the game records to DIR
+    help                         show this message";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (cmd, args) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    match cmd.as_str() {
+        "analyze" => analyze::analyze(args),
+        "bench-movegen" => bench::movegen(args),
+        "bench-ordering" => bench::ordering(args),
+        "gen-puzzles" => puzzle::generate(args),
+        "ladder" => ladder::ladder(args),
+        "perft" => perft::perft(args),
+        "replay" => replay::replay(args),
+        "solve" => puzzle::solve(args),
+        "train" => train::train(args),
+        "verify-perft" => verify::perft(args),
+        "verify-features" => verify::features(args),
+        "verify-search" => verify::search(args),
+        "verify-symmetry" => verify::symmetry(args),
+        "versus" => versus::versus(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("unknown command: {}\n\n{}", cmd, USAGE)),
+    }
+}
+
+fn parse_arg<T>(args: &[String], idx: usize, default: T) -> Result<T, String>
+where
+    T: FromStr,
+{
+    match args.get(idx) {
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("invalid argument: {}", arg)),
+        None => Ok(default),
+    }
+}
+
+struct Options {
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    // Parses `--name value` pairs, accepting only the given names.
+    fn parse(args: &[String], names: &[&str]) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .filter(|name| names.contains(name))
+                .ok_or_else(|| format!("unknown option: {}", arg))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            let _ = values.insert(name.to_string(), value.clone());
+        }
+        Ok(Options { values })
+    }
+
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|s| s.as_str())
+    }
+
+    fn get<T>(&self, name: &str, default: T) -> Result<T, String>
+    where
+        T: FromStr,
+    {
+        match self.get_str(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("invalid value for --{}: {}", name, value)),
+            None => Ok(default),
+        }
+    }
+}
+
+// How games of the tools playing many start: `--start`, `--first`,
+// `--rules`, `--topology`, `--obstacles`, `--random-plies` and `--balance`.
+struct Opening {
+    start: StartPosition,
+    first: Side,
+    rules: Rules,
+    topology: Topology,
+    obstacles: u32,
+    random_plies: u32,
+    balance: Option<f64>,
+}
+
+impl Opening {
+    const OPTIONS: &'static [&'static str] = &[
+        "start",
+        "first",
+        "rules",
+        "topology",
+        "obstacles",
+        "random-plies",
+        "balance",
+    ];
+
+    fn parse(opts: &Options, default_plies: u32) -> Result<Self, String> {
+        let start = match opts.get_str("start") {
+            Some(s) => *StartPosition::all_values()
+                .iter()
+                .find(|start| start.as_ref() == s)
+                .ok_or_else(|| format!("invalid value for --start: {}", s))?,
+            None => StartPosition::Crossed,
+        };
+        let first = match opts.get_str("first") {
+            Some(s) => *Side::all_values()
+                .iter()
+                .find(|side| side.as_ref() == s)
+                .ok_or_else(|| format!("invalid value for --first: {}", s))?,
+            None => Side::Black,
+        };
+        let rules = match opts.get_str("rules") {
+            Some(s) => *Rules::all_values()
+                .iter()
+                .find(|rules| rules.as_ref() == s)
+                .ok_or_else(|| format!("invalid value for --rules: {}", s))?,
+            None => Rules::Standard,
+        };
+        let topology = match opts.get_str("topology") {
+            Some(s) => *Topology::all_values()
+                .iter()
+                .find(|topology| topology.as_ref() == s)
+                .ok_or_else(|| format!("invalid value for --topology: {}", s))?,
+            None => Topology::Planar,
+        };
+        let balance = match opts.get_str("balance") {
+            Some(_) => Some(opts.get("balance", 0.0)?),
+            None => None,
+        };
+        Ok(Opening {
+            start,
+            first,
+            rules,
+            topology,
+            obstacles: opts.get("obstacles", 0)?,
+            random_plies: opts.get("random-plies", default_plies)?,
+            balance,
+        })
+    }
+
+    // Sets the start of `record` and plays the random opening moves into it,
+    // returning the position after them.
+    fn play<R>(&self, record: &mut GameRecord, rng: &mut R) -> Board
+    where
+        R: Rng,
+    {
+        record.start = self.start;
+        record.first = self.first;
+        record.rules = self.rules;
+        record.topology = self.topology;
+        record.blocked = random_obstacles(&record.initial_board(), self.obstacles, rng);
+        let board = record.initial_board();
+        record.moves = random_opening(&board, self.random_plies, self.balance, rng);
+        record.opening = record.moves.len();
+        record.start_board().unwrap()
+    }
+}
+
+// `COLSxROWS`, or a single number for square boards.
+fn parse_size(s: &str) -> Result<Size, String> {
+    let invalid = || format!("invalid board size: {}", s);
+    let mut it = s.splitn(2, 'x').map(|n| n.parse::<u32>());
+    let cols = it.next().unwrap().map_err(|_| invalid())?;
+    let rows = it.next().unwrap_or(Ok(cols)).map_err(|_| invalid())?;
+    let valid = MIN_SIZE..=MAX_SIZE;
+    if valid.contains(&cols) && valid.contains(&rows) {
+        Ok(Size(cols, rows))
+    } else {
+        Err(invalid())
+    }
+}
+
+// Positions reached by seeded random play from `start`, two for every fourth
+// ply.
+pub(crate) fn random_positions(start: &Board, seed: u64) -> Vec<Board> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let size = start.size();
+    let mut positions = vec![];
+    for plies in (4..(size.0 * size.1)).step_by(4) {
+        for _ in 0..2 {
+            let mut board = *start;
+            for _ in 0..plies {
+                let pt = match board.move_candidates().points(size).choose(&mut rng) {
+                    Some(pt) => pt,
+                    None => break,
+                };
+                board = board.make_move(pt).unwrap();
+            }
+            if board.turn().is_some() {
+                positions.push(board);
+            }
+        }
+    }
+    positions
+}
@@ -0,0 +1,367 @@
+use super::{parse_size, Options};
+use crate::model::{
+    format_position, BitBoard, Board, Move, Point, Side, Size, Topology, MAX_SIZE, MIN_SIZE,
+};
+use std::time::Instant;
+
+// Leaves of the 8x8 game tree by depth from 1, as published for Othello;
+// the first passes and finished games are at depth 9.
+pub(super) const PERFT_8X8: [u64; 11] = [
+    4, 12, 56, 244, 1396, 8200, 55092, 390216, 3005288, 24571284, 212258800,
+];
+
+// Counts the leaf positions of the game tree of every board size to a depth,
+// cross-checking the counts with a cell by cell move generator.
+pub fn perft(args: &[String]) -> Result<(), String> {
+    let opts = Options::parse(args, &["size", "topology", "depth", "reference-depth"])?;
+    let sizes = match opts.get_str("size") {
+        Some(size) => vec![parse_size(size)?],
+        None => all_sizes(),
+    };
+    let topology = match opts.get_str("topology") {
+        Some(s) => *Topology::all_values()
+            .iter()
+            .find(|topology| topology.as_ref() == s)
+            .ok_or_else(|| format!("invalid value for --topology: {}", s))?,
+        None => Topology::Planar,
+    };
+    let depth = opts.get("depth", 6)?;
+    let reference_depth = opts.get("reference-depth", depth)?;
+
+    println!(
+        "{:>5} {:>5} {:>14} {:>9}",
+        "size", "depth", "leaves", "time"
+    );
+    for &size in &sizes {
+        let board = Board::new(size).with_topology(topology);
+        for d in 1..=depth {
+            let start = Instant::now();
+            let leaves = count(&board, d);
+            let elapsed = start.elapsed();
+            if d <= reference_depth {
+                let reference = reference_count(&board, d)?;
+                if reference != leaves {
+                    return Err(format!(
+                        "{}x{} depth {}: {} leaves, the reference counts {}",
+                        size.0, size.1, d, leaves, reference
+                    ));
+                }
+            }
+            println!(
+                "{:>5} {:>5} {:>14} {:>8.3}s",
+                format!("{}x{}", size.0, size.1),
+                d,
+                leaves,
+                elapsed.as_secs_f64()
+            );
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn all_sizes() -> Vec<Size> {
+    (MIN_SIZE..=MAX_SIZE)
+        .flat_map(|rows| (MIN_SIZE..=MAX_SIZE).map(move |cols| Size(cols, rows)))
+        .collect()
+}
+
+/// Leaf positions `depth` plies below `board`. A pass is a ply of its own,
+/// and a finished game is a leaf however few plies it took.
+pub(super) fn count(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let turn = match board.turn() {
+        Some(turn) => turn,
+        None => return 1,
+    };
+    // Only a starting position is left to pass.
+    if board.must_pass() {
+        return count(&board.apply(Move::Pass).unwrap(), depth - 1);
+    }
+    let size = board.size();
+    board
+        .move_candidates()
+        .points(size)
+        .map(|pt| {
+            let child = board.make_move(pt).unwrap();
+            // `make_move` has already made the opponent pass if it must.
+            if child.turn() == Some(turn) {
+                if depth == 1 {
+                    1
+                } else {
+                    count(&child, depth - 2)
+                }
+            } else {
+                count(&child, depth - 1)
+            }
+        })
+        .sum()
+}
+
+/// Same as `count` by moves made and taken back in place, which leaves
+/// `board` as it was. The last ply is counted without making its moves.
+pub(super) fn count_in_place(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    board.settle();
+    let turn = match board.turn() {
+        Some(turn) => turn,
+        None => return 1,
+    };
+    if board.must_pass() {
+        let mut passed = board.apply(Move::Pass).unwrap();
+        return count_in_place(&mut passed, depth - 1);
+    }
+    let moves = board.move_candidates();
+    if depth == 1 {
+        return u64::from(moves.num_bits());
+    }
+    let mut leaves = 0;
+    for pt in moves.points(board.size()) {
+        let undo = board.play(pt).unwrap();
+        board.settle();
+        leaves += if board.turn() == Some(turn) {
+            count_in_place(board, depth - 2)
+        } else {
+            count_in_place(board, depth - 1)
+        };
+        board.undo(undo);
+    }
+    leaves
+}
+
+/// Same as `count` by the reference generator, checking every position
+/// against `Board` on the way. Fails with the first position they disagree
+/// on.
+pub(super) fn reference_count(board: &Board, depth: u32) -> Result<u64, String> {
+    walk(board, &Reference::from_board(board), depth)
+}
+
+fn walk(board: &Board, reference: &Reference, depth: u32) -> Result<u64, String> {
+    if depth == 0 {
+        return Ok(1);
+    }
+    let mismatch = |what: &str| format!("{} differ on {}", what, format_position(board));
+
+    let moves = reference.moves();
+    if moves.is_empty() {
+        let passed = reference.pass();
+        if passed.moves().is_empty() {
+            if board.turn().is_some() {
+                return Err(mismatch("game ends"));
+            }
+            return Ok(1);
+        }
+        // `board` has the side to move passed already, unless the game
+        // starts with the pass.
+        if board.must_pass() {
+            return walk(&board.apply(Move::Pass).unwrap(), &passed, depth - 1);
+        }
+        return walk(board, &passed, depth - 1);
+    }
+    if board.turn() != Some(reference.turn) {
+        return Err(mismatch("sides to move"));
+    }
+    let size = board.size();
+    if board
+        .move_candidates()
+        .points(size)
+        .ne(moves.iter().copied())
+    {
+        return Err(mismatch("moves"));
+    }
+
+    let mut leaves = 0;
+    for pt in moves {
+        let child = board.make_move(pt).ok_or_else(|| mismatch("moves"))?;
+        let ref_child = reference.play(pt);
+        if !ref_child.has_disks_of(&child) {
+            return Err(mismatch(&format!("disks after {:?}", pt)));
+        }
+        // The same move in place, then taken back.
+        let flips = reference
+            .flips(pt)
+            .into_iter()
+            .fold(BitBoard::empty(), |cells, p| {
+                cells | BitBoard::from_point(p, size)
+            });
+        let mut in_place = *board;
+        let undo = in_place
+            .play(pt)
+            .ok_or_else(|| mismatch("moves in place"))?;
+        in_place.settle();
+        if board.flips(pt) != Some(flips) || undo.flips(size) != flips || in_place != child {
+            return Err(mismatch(&format!("flips in place of {:?}", pt)));
+        }
+        in_place.undo(undo);
+        if in_place != *board || in_place.hash_key() != board.hash_key() {
+            return Err(mismatch(&format!("undoing {:?}", pt)));
+        }
+        // The same move with the opponent's pass left to be made.
+        let placed = board.apply(Move::Place(pt)).unwrap();
+        let must_pass = ref_child.moves().is_empty() && !ref_child.pass().moves().is_empty();
+        let passed = if must_pass {
+            placed.apply(Move::Pass)
+        } else {
+            Some(placed)
+        };
+        if placed.must_pass() != must_pass || passed != Some(child) {
+            return Err(mismatch(&format!("passes after {:?}", pt)));
+        }
+        leaves += walk(&child, &ref_child, depth - 1)?;
+    }
+    Ok(leaves)
+}
+
+// Board kept as one cell per point, every move found by walking from each
+// empty cell in the eight directions.
+#[derive(Clone, Debug)]
+struct Reference {
+    size: Size,
+    cells: Vec<Option<Side>>,
+    blocked: Vec<bool>,
+    torus: bool,
+    turn: Side,
+}
+
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl Reference {
+    fn from_board(board: &Board) -> Self {
+        let size = board.size();
+        Reference {
+            size,
+            cells: points(size).map(|pt| board.get(pt)).collect(),
+            blocked: points(size)
+                .map(|pt| board.blocked_cells().contains(pt, size))
+                .collect(),
+            torus: board.topology() == Topology::Torus,
+            turn: board.turn().unwrap_or(Side::Black),
+        }
+    }
+
+    fn get(&self, Point(x, y): Point) -> Option<Side> {
+        self.cells[(y * self.size.0 + x) as usize]
+    }
+
+    fn is_blocked(&self, Point(x, y): Point) -> bool {
+        self.blocked[(y * self.size.0 + x) as usize]
+    }
+
+    fn has_disks_of(&self, board: &Board) -> bool {
+        points(self.size).all(|pt| self.get(pt) == board.get(pt))
+    }
+
+    // The legal moves in the order of `BitBoard::points`.
+    fn moves(&self) -> Vec<Point> {
+        points(self.size)
+            .filter(|&pt| !self.flips(pt).is_empty())
+            .collect()
+    }
+
+    fn flips(&self, pt: Point) -> Vec<Point> {
+        if self.get(pt).is_some() || self.is_blocked(pt) {
+            return vec![];
+        }
+        let mut flips = vec![];
+        for &(dx, dy) in &DIRECTIONS {
+            let mut line = vec![];
+            let mut cur = self.step(pt, dx, dy);
+            while let Some(p) = cur {
+                match self.get(p) {
+                    Some(side) if side == self.turn => {
+                        flips.extend(line);
+                        break;
+                    }
+                    Some(_) => line.push(p),
+                    // empty or blocked
+                    None => break,
+                }
+                cur = self.step(p, dx, dy);
+            }
+        }
+        flips
+    }
+
+    // On a torus the walk comes back round to the empty cell it started
+    // from, which ends it.
+    fn step(&self, Point(x, y): Point, dx: i32, dy: i32) -> Option<Point> {
+        let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+        let x = x as i32 + dx;
+        let y = y as i32 + dy;
+        if self.torus {
+            return Some(Point(x.rem_euclid(w) as u32, y.rem_euclid(h) as u32));
+        }
+        let inside = 0 <= x && x < w && 0 <= y && y < h;
+        inside.then_some(Point(x as u32, y as u32))
+    }
+
+    fn play(&self, pt: Point) -> Self {
+        let mut next = self.pass();
+        for p in self.flips(pt).into_iter().chain(Some(pt)) {
+            next.cells[(p.1 * self.size.0 + p.0) as usize] = Some(self.turn);
+        }
+        next
+    }
+
+    fn pass(&self) -> Self {
+        Reference {
+            turn: self.turn.flip(),
+            ..self.clone()
+        }
+    }
+}
+
+// Row by row from the top left.
+pub(super) fn points(size: Size) -> impl Iterator<Item = Point> {
+    (0..size.1).flat_map(move |y| (0..size.0).map(move |x| Point(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::random_positions;
+
+    #[test]
+    fn perft_8x8() {
+        let mut board = Board::new(Size(8, 8));
+        for (d, &expected) in (1..=6).zip(&PERFT_8X8) {
+            assert_eq!(count(&board, d), expected, "depth {}", d);
+            assert_eq!(count_in_place(&mut board, d), expected, "depth {}", d);
+            assert_eq!(board, Board::new(Size(8, 8)));
+        }
+        assert_eq!(reference_count(&board, 6), Ok(PERFT_8X8[5]));
+    }
+
+    #[test]
+    fn play_and_undo() {
+        for &size in &[Size(4, 4), Size(5, 7), Size(8, 8), Size(10, 6)] {
+            for board in random_positions(&Board::new(size), 1) {
+                for pt in points(size) {
+                    let mut in_place = board;
+                    match in_place.play(pt) {
+                        Some(undo) => {
+                            in_place.settle();
+                            assert_eq!(Some(in_place), board.make_move(pt));
+                            in_place.undo(undo);
+                            assert_eq!(in_place, board);
+                            assert_eq!(in_place.hash_key(), board.hash_key());
+                        }
+                        None => assert_eq!(board.make_move(pt), None),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::{parse_size, Options};
+use crate::model::{
+    self, format_point, format_position, AlphaBetaPlayer, Board, FindMove, Side, Size,
+    StrongEvaluator,
+};
+use rand::{rngs::StdRng, seq::IteratorRandom as _, SeedableRng as _};
+use std::{collections::HashSet, fs, time::Instant};
+
+// Largest winning margin of generated puzzles.
+const MAX_PUZZLE_SCORE: i32 = 16;
+
+const OPTIONS: &[&str] = &[
+    "size",
+    "puzzles",
+    "empties",
+    "num-eval",
+    "random-plies",
+    "seed",
+    "output",
+];
+
+// Picks positions with a single winning or drawing move from self-play
+// games, in the format of the built-in problem set.
+pub fn generate(args: &[String]) -> Result<(), String> {
+    let opts = Options::parse(args, OPTIONS)?;
+    let size = match opts.get_str("size") {
+        Some(s) => parse_size(s)?,
+        None => Size(8, 8),
+    };
+    let num_puzzles = opts.get("puzzles", 20)?;
+    let empties = opts.get("empties", 12)?;
+    let num_eval = opts.get("num-eval", 10_000)?;
+    let random_plies = opts.get("random-plies", 10)?;
+    let mut rng = StdRng::seed_from_u64(opts.get("seed", 1)?);
+
+    let mut black = AlphaBetaPlayer::new(Side::Black, num_eval, StrongEvaluator::new(size));
+    let mut white = AlphaBetaPlayer::new(Side::White, num_eval, StrongEvaluator::new(size));
+    let mut seen = HashSet::new();
+    let mut lines = vec![];
+    while lines.len() < num_puzzles {
+        let mut board = Board::new(size);
+        let mut ply = 0;
+        while let Some(turn) = board.turn() {
+            if board.num_empty() == empties {
+                break;
+            }
+            let pt = if ply < random_plies {
+                board
+                    .move_candidates()
+                    .points(size)
+                    .choose(&mut rng)
+                    .unwrap()
+            } else {
+                match turn {
+                    Side::Black => black.find_move(board),
+                    Side::White => white.find_move(board),
+                }
+            };
+            board = board.make_move(pt).unwrap();
+            ply += 1;
+        }
+        if board.turn().is_none()
+            || board.move_candidates().num_bits() < 2
+            || !seen.insert(board.canonical_hash_key())
+        {
+            continue;
+        }
+        let solution = model::solve(&board).unwrap();
+        // Puzzles need a single right answer, in a game that is still close.
+        if solution.best_moves.len() != 1 || !(0..=MAX_PUZZLE_SCORE).contains(&solution.score) {
+            continue;
+        }
+        lines.push(format!("{} ; {}", format_position(&board), solution));
+        eprintln!("{} / {}", lines.len(), num_puzzles);
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    match opts.get_str("output") {
+        Some(output) => fs::write(output, text).map_err(|e| format!("{}: {}", output, e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+// Solves every puzzle of a problem file, or the built-in ones.
+pub fn solve(args: &[String]) -> Result<(), String> {
+    let puzzles = match args.first() {
+        Some(path) => {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            model::parse_puzzles(&text)?
+        }
+        None => model::builtin_puzzles(),
+    };
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        let start = Instant::now();
+        let solution = model::solve(&puzzle.board).unwrap();
+        let line = solution
+            .line
+            .iter()
+            .map(|&pt| format_point(pt))
+            .collect::<Vec<_>>();
+        println!(
+            "{:>3}. {}: {} in {:.2?}\n     {}",
+            i + 1,
+            format_position(&puzzle.board),
+            solution,
+            start.elapsed(),
+            line.join(" ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use super::versus;
+use crate::model::{GameRecord, PlayerKind, Side};
+use std::fs;
+
+// Replays an AI game from its record by the recorded seed, and reports
+// whether the AIs make the recorded moves again.
+pub fn replay(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or("replay needs a record file")?;
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let record = text.parse::<GameRecord>()?;
+    if record.black == PlayerKind::Human || record.white == PlayerKind::Human {
+        return Err("only games between AIs can be replayed".to_string());
+    }
+    let board = record.start_board().ok_or("illegal move in the opening")?;
+
+    let mut replayed = GameRecord {
+        moves: record.moves[..record.opening].to_vec(),
+        ..record.clone()
+    };
+    let (board, _) = versus::play(board, &mut replayed)?;
+    print!("{}", replayed);
+
+    let diverged = record
+        .moves
+        .iter()
+        .zip(&replayed.moves)
+        .position(|(a, b)| a != b)
+        .or_else(|| {
+            (record.moves.len() != replayed.moves.len())
+                .then(|| usize::min(record.moves.len(), replayed.moves.len()))
+        });
+    match diverged {
+        None => {
+            println!(
+                "replayed {} moves as recorded, final disks {}-{}",
+                replayed.moves.len(),
+                board.num_disk(Side::Black),
+                board.num_disk(Side::White)
+            );
+            Ok(())
+        }
+        Some(ply) => Err(format!(
+            "the replay differs from the record at move {}",
+            ply + 1
+        )),
+    }
+}
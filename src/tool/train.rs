@@ -0,0 +1,163 @@
+use super::{parse_size, Options};
+use crate::model::{
+    AlphaBetaPlayer, Board, FindMove, PatternEvaluator, PatternWeights, Patterns, Score, Side,
+    Size, StrongEvaluator,
+};
+use rand::{rngs::StdRng, seq::IteratorRandom as _, seq::SliceRandom as _, Rng as _, SeedableRng};
+use std::{fs, path::PathBuf};
+
+const OPTIONS: &[&str] = &[
+    "size",
+    "games",
+    "num-eval",
+    "random-plies",
+    "epsilon",
+    "exact-empties",
+    "phases",
+    "epochs",
+    "rate",
+    "seed",
+    "output",
+];
+
+struct Sample {
+    board: Board,
+    // final disk difference for black
+    label: f32,
+}
+
+pub fn train(args: &[String]) -> Result<(), String> {
+    let opts = Options::parse(args, OPTIONS)?;
+    let size = match opts.get_str("size") {
+        Some(s) => parse_size(s)?,
+        None => Size(8, 8),
+    };
+    let seed = opts.get("seed", 1)?;
+    let output = opts
+        .get_str("output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PatternEvaluator::default_path(size));
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let games = SelfPlay {
+        size,
+        num_eval: opts.get("num-eval", 1000)?,
+        random_plies: opts.get("random-plies", 8)?,
+        epsilon: opts.get("epsilon", 0.1)?,
+        exact_empties: opts.get("exact-empties", 0)?,
+    };
+    let mut samples = games.generate(opts.get("games", 1000)?, &mut rng);
+    samples.shuffle(&mut rng);
+    let num_test = samples.len() / 10;
+    let (test, train) = samples.split_at_mut(num_test);
+    println!("{} training and {} test positions", train.len(), test.len());
+
+    let patterns = Patterns::new(size);
+    let mut weights = PatternWeights::zero(&patterns, opts.get("phases", 6)?);
+    let rate = opts.get("rate", 0.1)?;
+    for epoch in 0..opts.get("epochs", 20)? {
+        train.shuffle(&mut rng);
+        let mut train_error = 0.0;
+        for sample in train.iter() {
+            let error = weights.train(&patterns, &sample.board, sample.label, rate);
+            train_error += f64::from(error * error);
+        }
+        let test_error = test
+            .iter()
+            .map(|sample| {
+                let error = sample.label - weights.predict(&patterns, &sample.board);
+                f64::from(error * error)
+            })
+            .sum::<f64>();
+        println!(
+            "epoch {:>3}: train rmse {:.3}, test rmse {:.3}",
+            epoch + 1,
+            (train_error / train.len() as f64).sqrt(),
+            (test_error / test.len().max(1) as f64).sqrt()
+        );
+    }
+
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    weights
+        .save(&output)
+        .map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("wrote {}", output.display());
+    Ok(())
+}
+
+struct SelfPlay {
+    size: Size,
+    num_eval: u32,
+    random_plies: u32,
+    epsilon: f64,
+    exact_empties: u32,
+}
+
+impl SelfPlay {
+    fn generate(&self, num_games: u32, rng: &mut StdRng) -> Vec<Sample> {
+        let mut black = AlphaBetaPlayer::new(Side::Black, self.num_eval, self.evaluator());
+        let mut white = AlphaBetaPlayer::new(Side::White, self.num_eval, self.evaluator());
+        // The exact searches of all the games share a table per side.
+        let mut solvers =
+            Side::all_values().map(|side| AlphaBetaPlayer::new(side, u32::MAX, self.evaluator()));
+
+        let mut samples = vec![];
+        for _ in 0..num_games {
+            let mut board = Board::new(self.size);
+            let mut positions = vec![];
+            let mut ply = 0;
+            while let Some(turn) = board.turn() {
+                positions.push(board);
+                let random = ply < self.random_plies || rng.gen_bool(self.epsilon);
+                let pt = if random {
+                    board
+                        .move_candidates()
+                        .points(self.size)
+                        .choose(rng)
+                        .unwrap()
+                } else {
+                    match turn {
+                        Side::Black => black.find_move(board),
+                        Side::White => white.find_move(board),
+                    }
+                };
+                board = board.make_move(pt).unwrap();
+                ply += 1;
+            }
+
+            let result = board.num_disk(Side::Black) as f32 - board.num_disk(Side::White) as f32;
+            for position in positions {
+                let label = self.exact_label(&mut solvers, &position).unwrap_or(result);
+                samples.push(Sample {
+                    board: position,
+                    label,
+                });
+            }
+        }
+        samples
+    }
+
+    fn evaluator(&self) -> StrongEvaluator {
+        StrongEvaluator::new(self.size)
+    }
+
+    // Perfect play result for black, for positions near the end of the game.
+    fn exact_label(
+        &self,
+        solvers: &mut [AlphaBetaPlayer<StrongEvaluator>; 2],
+        board: &Board,
+    ) -> Option<f32> {
+        let num_empty = board.num_empty();
+        if num_empty > self.exact_empties {
+            return None;
+        }
+        let turn = board.turn()?;
+        match (solvers[turn.to_index()].search(board).1, turn) {
+            (Score::Ended(v), Side::Black) => Some(v as f32),
+            (Score::Ended(v), Side::White) => Some(-v as f32),
+            _ => None,
+        }
+    }
+}
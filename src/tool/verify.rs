@@ -0,0 +1,501 @@
+use super::{parse_arg, perft as pf, random_positions};
+use crate::model::{
+    format_point, format_position, random_obstacles, AlphaBetaPlayer, BitBoard, Board, Evaluate,
+    EvenEvaluator, Point, Score, Side, Size, StrongEvaluator, Symmetry, Topology, WeakEvaluator,
+    MAX_SCORE, MIN_SCORE,
+};
+use rand::{rngs::StdRng, seq::IteratorRandom as _, SeedableRng as _};
+use std::cmp;
+
+const CORPUS_SEED: u64 = 0xfedc_ba98_7654_3210;
+
+pub fn search(args: &[String]) -> Result<(), String> {
+    let num_eval = parse_arg(args, 0, 100_000)?;
+    let num_threads = parse_arg(args, 1, 1)?;
+
+    let mut num_searches = 0;
+    let mut failures = vec![];
+    for &size in &[Size(4, 4), Size(5, 6), Size(6, 6), Size(8, 8)] {
+        let positions = random_positions(&Board::new(size), CORPUS_SEED);
+        let results = [
+            (
+                "strong",
+                check(&positions, num_eval, num_threads, || {
+                    StrongEvaluator::new(size)
+                }),
+            ),
+            (
+                "even",
+                check(&positions, num_eval, num_threads, || {
+                    EvenEvaluator::new(size)
+                }),
+            ),
+            (
+                "weak",
+                check(&positions, num_eval, num_threads, || {
+                    WeakEvaluator::new(size)
+                }),
+            ),
+        ];
+        for (name, errors) in &results {
+            num_searches += positions.len();
+            for msg in errors {
+                failures.push(format!("{}x{} {}: {}", size.0, size.1, name, msg));
+            }
+        }
+    }
+
+    for msg in &failures {
+        println!("{}", msg);
+    }
+    println!(
+        "{} / {} searches agree with the reference",
+        num_searches - failures.len(),
+        num_searches
+    );
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err("search mismatch".to_string())
+    }
+}
+
+// Checks that transformed positions have the transformed moves and results,
+// and share their canonical form.
+pub fn symmetry(_args: &[String]) -> Result<(), String> {
+    let mut num_checks = 0;
+    let mut failures = vec![];
+    for &size in &[Size(4, 4), Size(3, 8), Size(5, 6), Size(6, 6), Size(8, 8)] {
+        for (i, board) in random_positions(&Board::new(size), CORPUS_SEED)
+            .iter()
+            .enumerate()
+        {
+            let mut fail = |msg: String| {
+                failures.push(format!("{}x{} position {}: {}", size.0, size.1, i, msg))
+            };
+            let canonical = board.canonical_hash_key();
+            for &sym in &Symmetry::all_values() {
+                num_checks += 1;
+                let transformed = board.transform(sym);
+                if transformed.transform(sym.inverse()) != *board {
+                    fail(format!("{:?} is not undone by its inverse", sym));
+                }
+                let turn = transformed.turn().unwrap();
+                let recomputed = Board::from_cells(
+                    transformed.size(),
+                    transformed.black_cells(),
+                    transformed.white_cells(),
+                    turn,
+                );
+                if recomputed.move_candidates() != transformed.move_candidates() {
+                    fail(format!("{:?} moves the move candidates wrongly", sym));
+                }
+                if recomputed.hash_key() != transformed.hash_key() {
+                    fail(format!("{:?} changes the hash of equal positions", sym));
+                }
+                for pt in board.move_candidates().points(size) {
+                    let moved = board.make_move(pt).unwrap().transform(sym);
+                    let moved_after = transformed.make_move(sym.apply(pt, size));
+                    if moved_after != Some(moved) {
+                        fail(format!("{:?} does not commute with move {:?}", sym, pt));
+                    }
+                    // The incremental hash matches the one computed afresh.
+                    if moved_after.map(|b| b.hash_key()) != Some(moved.hash_key()) {
+                        fail(format!("move {:?} updates the hash wrongly", pt));
+                    }
+                }
+                let preserves_size = Symmetry::of_size(size).contains(&sym);
+                if preserves_size && transformed.canonical_hash_key() != canonical {
+                    fail(format!("{:?} changes the canonical form", sym));
+                }
+            }
+        }
+    }
+    // The four first moves of the standard opening are all alike.
+    let start = Board::new(Size(8, 8));
+    let first_moves = start
+        .move_candidates()
+        .points(Size(8, 8))
+        .map(|pt| start.make_move(pt).unwrap().canonical_hash_key())
+        .collect::<Vec<_>>();
+    num_checks += 1;
+    if first_moves.iter().any(|&k| k != first_moves[0]) {
+        failures.push("first moves differ in canonical form".to_string());
+    }
+
+    for msg in &failures {
+        println!("{}", msg);
+    }
+    println!(
+        "{} / {} symmetry checks passed",
+        num_checks - failures.len(),
+        num_checks
+    );
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err("symmetry mismatch".to_string())
+    }
+}
+
+// Checks the stable disks of random positions by playing random games to the
+// end from them, and the other features against cell by cell counts.
+pub fn features(_args: &[String]) -> Result<(), String> {
+    let mut rng = StdRng::seed_from_u64(CORPUS_SEED);
+    let mut num_checks = 0;
+    let mut num_stable = 0;
+    let mut failures = vec![];
+    for &size in &[Size(4, 4), Size(3, 8), Size(6, 6), Size(8, 8), Size(10, 10)] {
+        let start = Board::new(size);
+        let blocked = random_obstacles(&start, size.0 * size.1 / 8, &mut rng)
+            .into_iter()
+            .fold(BitBoard::empty(), |cells, pt| {
+                cells | BitBoard::from_point(pt, size)
+            });
+        let starts = [
+            start,
+            start.with_blocked(blocked),
+            start.with_topology(Topology::Torus),
+        ];
+        for start in &starts {
+            for board in random_positions(start, CORPUS_SEED) {
+                let stable = board.stable_disks();
+                num_stable += stable.num_bits();
+                let checks = [
+                    ("stable disks", check_stable(&board, stable, &mut rng)),
+                    ("features", check_features(&board)),
+                ];
+                for (what, result) in checks {
+                    num_checks += 1;
+                    if let Err(msg) = result {
+                        failures.push(format!("{} of {}: {}", what, format_position(&board), msg));
+                    }
+                }
+            }
+        }
+    }
+
+    for msg in &failures {
+        println!("{}", msg);
+    }
+    println!(
+        "{} / {} feature checks passed, {} stable disks found",
+        num_checks - failures.len(),
+        num_checks,
+        num_stable
+    );
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err("feature mismatch".to_string())
+    }
+}
+
+// Random games to the end from `board`, none of which may flip a disk of
+// `stable`.
+fn check_stable(board: &Board, stable: BitBoard, rng: &mut StdRng) -> Result<(), String> {
+    const PLAYOUTS: u32 = 20;
+
+    let size = board.size();
+    let disks = |board: &Board| Side::all_values().map(|side| board.cells(side) & stable);
+    let expected = disks(board);
+    if (expected[0] | expected[1]) != stable {
+        return Err("empty or blocked cells among them".to_string());
+    }
+    for _ in 0..PLAYOUTS {
+        let mut board = *board;
+        while let Some(pt) = board.move_candidates().points(size).choose(rng) {
+            board = board.make_move(pt).unwrap();
+            if disks(&board) != expected {
+                return Err(format!("{} flips one", format_point(pt)));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_features(board: &Board) -> Result<(), String> {
+    let size = board.size();
+    let cells = |pred: &dyn Fn(Point) -> bool| {
+        pf::points(size)
+            .filter(|&pt| pred(pt))
+            .fold(BitBoard::empty(), |cells, pt| {
+                cells | BitBoard::from_point(pt, size)
+            })
+    };
+    let is_empty = |pt| board.get(pt).is_none() && !board.blocked_cells().contains(pt, size);
+    let next_to = |pt, pred: &dyn Fn(Point) -> bool| neighbours(board, pt).into_iter().any(pred);
+
+    for side in Side::all_values() {
+        let frontier = cells(&|pt| board.get(pt) == Some(side) && next_to(pt, &is_empty));
+        if board.frontier_disks(side) != frontier {
+            return Err(format!("frontier disks of {} differ", side.as_ref()));
+        }
+        let potential =
+            cells(&|pt| is_empty(pt) && next_to(pt, &|p| board.get(p) == Some(side.flip())));
+        if board.potential_mobility(side) != potential.num_bits() {
+            return Err(format!("potential mobility of {} differs", side.as_ref()));
+        }
+    }
+
+    let planar = board.topology() == Topology::Planar;
+    let (w, h) = (size.0 - 1, size.1 - 1);
+    let corners = cells(&|Point(x, y)| planar && (x == 0 || x == w) && (y == 0 || y == h));
+    if board.corner_cells() != corners {
+        return Err("corners differ".to_string());
+    }
+    let edges = cells(&|Point(x, y)| planar && (x == 0 || x == w || y == 0 || y == h));
+    if board.edge_cells() != edges & !corners {
+        return Err("edges differ".to_string());
+    }
+    for side in Side::all_values() {
+        let count = |mask: BitBoard| (cells(&|pt| board.get(pt) == Some(side)) & mask).num_bits();
+        if board.occupied_corners(side) != count(corners)
+            || board.occupied_edges(side) != count(edges & !corners)
+        {
+            return Err(format!("corners or edges of {} differ", side.as_ref()));
+        }
+    }
+
+    // Regions by a search from each empty cell not in one yet.
+    let mut regions: Vec<BitBoard> = vec![];
+    for pt in pf::points(size).filter(|&pt| is_empty(pt)) {
+        if regions.iter().any(|region| region.contains(pt, size)) {
+            continue;
+        }
+        let mut region = BitBoard::empty();
+        let mut stack = vec![pt];
+        while let Some(p) = stack.pop() {
+            if is_empty(p) && !region.contains(p, size) {
+                region |= BitBoard::from_point(p, size);
+                stack.extend(neighbours(board, p));
+            }
+        }
+        regions.push(region);
+    }
+    if board.parity_regions() != regions {
+        return Err("parity regions differ".to_string());
+    }
+    Ok(())
+}
+
+// The cells next to `pt`, wrapping around the edges of a torus.
+fn neighbours(board: &Board, Point(x, y): Point) -> Vec<Point> {
+    let size = board.size();
+    let (w, h) = (size.0 as i32, size.1 as i32);
+    let torus = board.topology() == Topology::Torus;
+    let mut pts = vec![];
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if (dx, dy) == (0, 0) {
+                continue;
+            }
+            if torus {
+                pts.push(Point(nx.rem_euclid(w) as u32, ny.rem_euclid(h) as u32));
+            } else if 0 <= nx && nx < w && 0 <= ny && ny < h {
+                pts.push(Point(nx as u32, ny as u32));
+            }
+        }
+    }
+    pts
+}
+
+// Checks the leaf counts of the 8x8 game tree against the published ones,
+// and those of every size against the reference move generator: to the end
+// of the game for boards of at most 16 cells, to 6 plies otherwise.
+pub fn perft(args: &[String]) -> Result<(), String> {
+    let depth = parse_arg(args, 0, 9)?;
+    let reference_depth = parse_arg(args, 1, 7)?;
+
+    let mut num_checks = 0;
+    let mut failures = vec![];
+    let start = Board::new(Size(8, 8));
+    for (d, &expected) in (1..=depth).zip(&pf::PERFT_8X8) {
+        num_checks += 1;
+        let leaves = pf::count(&start, d);
+        if leaves != expected {
+            failures.push(format!(
+                "8x8 depth {}: {}, expected {}",
+                d, leaves, expected
+            ));
+        }
+        num_checks += 1;
+        if let Err(msg) = check_in_place(&start, d, expected) {
+            failures.push(format!("8x8 depth {}: {}", d, msg));
+        }
+        if d <= reference_depth {
+            num_checks += 1;
+            if let Err(msg) = pf::reference_count(&start, d) {
+                failures.push(format!("8x8 depth {}: {}", d, msg));
+            }
+        }
+    }
+    // Every size from the start, then with a few cells blocked, then with the
+    // edges wrapping around.
+    let mut rng = StdRng::seed_from_u64(CORPUS_SEED);
+    for size in pf::all_sizes() {
+        let num_cells = size.0 * size.1;
+        // Passes are plies too, so no game lasts more than twice the cells.
+        let depth = if num_cells <= 16 { 2 * num_cells } else { 6 };
+        let start = Board::new(size);
+        let blocked = random_obstacles(&start, num_cells / 8, &mut rng)
+            .into_iter()
+            .fold(BitBoard::empty(), |cells, pt| {
+                cells | BitBoard::from_point(pt, size)
+            });
+        let torus = start.with_topology(Topology::Torus);
+        for board in [start, start.with_blocked(blocked), torus] {
+            num_checks += 2;
+            let name = format_position(&board);
+            if let Err(msg) = check_in_place(&board, depth, pf::count(&board, depth)) {
+                failures.push(format!("{} depth {}: {}", name, depth, msg));
+            }
+            match pf::reference_count(&board, depth) {
+                Ok(reference) if reference == pf::count(&board, depth) => {}
+                Ok(reference) => failures.push(format!(
+                    "{} depth {}: {}, the reference counts {}",
+                    name,
+                    depth,
+                    pf::count(&board, depth),
+                    reference
+                )),
+                Err(msg) => failures.push(format!("{} depth {}: {}", name, depth, msg)),
+            }
+        }
+    }
+
+    for msg in &failures {
+        println!("{}", msg);
+    }
+    println!(
+        "{} / {} perft checks passed",
+        num_checks - failures.len(),
+        num_checks
+    );
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err("perft mismatch".to_string())
+    }
+}
+
+// Counting by moves made in place must find `leaves` and leave the board as
+// it was.
+fn check_in_place(board: &Board, depth: u32, leaves: u64) -> Result<(), String> {
+    let mut in_place = *board;
+    let count = pf::count_in_place(&mut in_place, depth);
+    if count != leaves {
+        return Err(format!("{} leaves in place, expected {}", count, leaves));
+    }
+    if in_place != *board || in_place.hash_key() != board.hash_key() {
+        return Err("undoing the moves changed the board".to_string());
+    }
+    Ok(())
+}
+
+// Players are kept across positions so that aspiration windows start from
+// unrelated scores and their re-searches get exercised too.
+pub(crate) fn check<E, F>(
+    positions: &[Board],
+    num_eval: u32,
+    num_threads: usize,
+    evaluator: F,
+) -> Vec<String>
+where
+    E: Evaluate + Sync,
+    F: Fn() -> E,
+{
+    let mut players = [
+        AlphaBetaPlayer::new(Side::Black, num_eval, evaluator()).num_threads(num_threads),
+        AlphaBetaPlayer::new(Side::White, num_eval, evaluator()).num_threads(num_threads),
+    ];
+    let mut errors = vec![];
+    for (i, board) in positions.iter().enumerate() {
+        let side = board.turn().unwrap();
+        let reference = Reference {
+            side,
+            evaluator: evaluator(),
+        };
+        let scores = reference.root_scores(board, f64::from(num_eval));
+        let best = scores.iter().map(|e| e.1).max().unwrap();
+
+        let player = match side {
+            Side::Black => &mut players[0],
+            Side::White => &mut players[1],
+        };
+        let (pt, score) = player.search(board);
+        let picked = scores.iter().find(|e| e.0 == pt).unwrap().1;
+
+        if picked.cmp(&best) != cmp::Ordering::Equal || score.cmp(&best) != cmp::Ordering::Equal {
+            errors.push(format!(
+                "position {}: picked {:?} ({:?}, reported {:?}), best {:?}",
+                i, pt, picked, score, best
+            ));
+        }
+    }
+    errors
+}
+
+// The fail-hard minimax search which `AlphaBetaPlayer` replaced. Every root
+// move is searched with a full window so that its score is exact.
+struct Reference<E> {
+    side: Side,
+    evaluator: E,
+}
+
+impl<E> Reference<E>
+where
+    E: Evaluate,
+{
+    fn root_scores(&self, board: &Board, num_eval: f64) -> Vec<(Point, Score)> {
+        let cands = board.move_candidates();
+        let size = board.size();
+        let child_num_eval = num_eval / f64::from(cands.num_bits());
+        cands
+            .points(size)
+            .map(|pt| {
+                let child = board.make_move(pt).unwrap();
+                (
+                    pt,
+                    self.alphabeta(&child, child_num_eval, MIN_SCORE, MAX_SCORE),
+                )
+            })
+            .collect()
+    }
+
+    fn alphabeta(&self, board: &Board, num_eval: f64, alpha: Score, beta: Score) -> Score {
+        if num_eval <= 1.0 || board.turn().is_none() {
+            return self.evaluator.evaluate(board, self.side);
+        }
+
+        let cands = board.move_candidates();
+        let size = board.size();
+        let num_cands = cands.num_bits();
+        let child_num_eval = num_eval / f64::from(num_cands);
+
+        let it = cands.points(size).map(|pt| board.make_move(pt).unwrap());
+
+        if board.turn() == Some(self.side) {
+            let mut alpha = alpha;
+            for board in it {
+                let score = self.alphabeta(&board, child_num_eval, alpha, beta);
+                alpha = cmp::max(alpha, score);
+                if alpha >= beta {
+                    return beta;
+                }
+            }
+            alpha
+        } else {
+            let mut beta = beta;
+            for board in it {
+                let score = self.alphabeta(&board, child_num_eval, alpha, beta);
+                beta = cmp::min(beta, score);
+                if alpha >= beta {
+                    return alpha;
+                }
+            }
+            beta
+        }
+    }
+}
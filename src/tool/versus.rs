@@ -0,0 +1,139 @@
+use super::{parse_size, Opening, Options};
+use crate::model::{AiConfig, AiPlayer, Board, GameRecord, PlayerKind, Side, Size};
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+use std::{fs, path::Path, sync::mpsc::TryRecvError, thread, time::Duration};
+
+const OPTIONS: &[&str] = &[
+    "games", "size", "budget", "book", "threads", "ponder", "seed", "records",
+];
+
+pub fn versus(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err("versus needs two AI names".to_string());
+    }
+    let opts = Options::parse(&args[2..], &[OPTIONS, Opening::OPTIONS].concat())?;
+    let mut configs = [parse_preset(&args[0])?, parse_preset(&args[1])?];
+    for config in &mut configs {
+        if let Some(budget) = opts.get_str("budget") {
+            config.budget = budget.parse()?;
+        }
+        config.book = opts.get("book", false)?;
+        config.num_threads = opts.get("threads", 1)?;
+        config.ponder = opts.get("ponder", false)?;
+    }
+    let names = [args[0].as_str(), args[1].as_str()];
+    let size = match opts.get_str("size") {
+        Some(s) => parse_size(s)?,
+        None => Size(8, 8),
+    };
+    let num_games = opts.get("games", 10)?;
+    let opening = Opening::parse(&opts, 4)?;
+    let mut rng = StdRng::seed_from_u64(opts.get("seed", 1)?);
+    let records_dir = opts.get_str("records").map(Path::new);
+    if let Some(dir) = records_dir {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+
+    // wins, draws and losses of the first AI
+    let mut total = [0, 0, 0];
+    for game in 0..num_games {
+        // The AIs swap sides every game.
+        let first_side = if game % 2 == 0 {
+            Side::Black
+        } else {
+            Side::White
+        };
+        let (black, white) = match first_side {
+            Side::Black => (configs[0], configs[1]),
+            Side::White => (configs[1], configs[0]),
+        };
+        let mut record = GameRecord::new(size, 0, PlayerKind::Ai(black), PlayerKind::Ai(white));
+        let board = opening.play(&mut record, &mut rng);
+        record.seed = rng.gen();
+        let (board, [black_summary, white_summary]) = play(board, &mut record)?;
+        let summaries = match first_side {
+            Side::Black => [black_summary, white_summary],
+            Side::White => [white_summary, black_summary],
+        };
+        if let Some(dir) = records_dir {
+            let path = dir.join(format!("game-{:03}.txt", game + 1));
+            fs::write(&path, record.to_string())
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        let diff = board.outcome(first_side);
+        let outcome = if diff > 0 {
+            0
+        } else if diff == 0 {
+            1
+        } else {
+            2
+        };
+        total[outcome] += 1;
+        println!(
+            "game {:>3}: {} as {:?}, score {:+}, seed {}",
+            game + 1,
+            names[0],
+            first_side,
+            diff,
+            record.seed
+        );
+        for (name, summary) in names.iter().zip(&summaries) {
+            if let Some(summary) = summary {
+                println!("          {}: {}", name, summary);
+            }
+        }
+    }
+    println!(
+        "{} vs {}: {} wins, {} draws, {} losses",
+        names[0], names[1], total[0], total[1], total[2]
+    );
+    Ok(())
+}
+
+// Lets the AIs of `record` play from `board` with the record's seed, adding
+// their moves to it. Returns the final board and the summaries of the black
+// and white AIs.
+pub(super) fn play(
+    mut board: Board,
+    record: &mut GameRecord,
+) -> Result<(Board, [Option<String>; 2]), String> {
+    let seed = record.seed;
+    let players = [
+        AiPlayer::try_new(record.black, &board, Side::Black, seed)?.ok_or("black is not an AI")?,
+        AiPlayer::try_new(record.white, &board, Side::White, seed)?.ok_or("white is not an AI")?,
+    ];
+    let index = |side| match side {
+        Side::Black => 0,
+        Side::White => 1,
+    };
+
+    while let Some(turn) = board.turn() {
+        // The AIs pass by themselves too.
+        let mv = loop {
+            match players[index(turn)].listen() {
+                Ok(mv) => break mv,
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(1)),
+                Err(TryRecvError::Disconnected) => return Err("AI player died".to_string()),
+            }
+        };
+        board = board
+            .apply(mv)
+            .ok_or_else(|| format!("AI made an illegal move: {:?}", mv))?;
+        record.moves.push(mv);
+        players[index(turn.flip())]
+            .make_move(turn, mv)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok((board, players.map(AiPlayer::finish)))
+}
+
+fn parse_preset(name: &str) -> Result<AiConfig, String> {
+    AiConfig::from_slug(name).ok_or_else(|| {
+        let names = AiConfig::presets()
+            .iter()
+            .map(|preset| preset.slug())
+            .collect::<Vec<_>>();
+        format!("unknown AI: {} (one of {})", name, names.join(", "))
+    })
+}
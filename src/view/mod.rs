@@ -2,6 +2,7 @@ use crate::view_model::{App, State};
 use conrod_core::{widget_ids, UiCell};
 
 mod play;
+mod puzzle;
 mod start;
 mod widget;
 
@@ -11,26 +12,62 @@ widget_ids! {
         canvas,
 
         start_button,
+        puzzle_button,
         times_label,
         rows_ddl,
         cols_ddl,
+        start_ddl,
+        first_ddl,
+        opening_ddl,
+        rules_ddl,
+        topology_ddl,
+        obstacles_ddl,
         black_player_ddl,
         white_player_ddl,
+        ai_settings_toggle,
+        algorithm_ddls[],
+        evaluator_ddls[],
+        budget_kind_ddls[],
+        budget_ddls[],
+        strength_ddls[],
+        book_toggles[],
+        threads_ddls[],
+        ponder_toggles[],
 
         play_canvas,
         board,
         black_indicator,
         white_indicator,
+        info_text,
+        save_record_button,
+        begin_button,
         stop_button,
+        stable_toggle,
+        move_list,
+        analysis_depth_ddl,
+        analyze_button,
+
+        puzzle_text,
+        next_puzzle_button,
     }
 }
 
 pub fn set_widgets(ui: &mut UiCell<'_>, ids: &mut Ids, app: &mut App) {
     let new_state = match app.state {
-        State::Start => start::set_widgets(ui, ids, &mut app.game_config, &app.view_config),
+        State::Start => start::set_widgets(
+            ui,
+            ids,
+            &mut app.game_config,
+            &app.view_config,
+            app.max_threads,
+            &mut app.show_ai_settings,
+        ),
         State::Play(ref mut play) => {
             play::set_widgets(ui, ids, app.game_config, &app.view_config, play)
         }
+        State::Puzzle(ref mut puzzle) => {
+            puzzle::set_widgets(ui, ids, &app.view_config, puzzle, &mut app.puzzle_stats)
+        }
     };
 
     if let Some(new_state) = new_state {
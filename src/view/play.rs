@@ -3,12 +3,14 @@ use super::{
     Ids,
 };
 use crate::{
-    model::Side,
+    model::{format_move, format_point, Grade, Move, Rules, Side},
     view_model::{GameConfig, PlayState, State, ViewConfig},
 };
 use conrod_core::{
-    color::Colorable,
-    widget::{line::Style as LineStyle, Button, Canvas, Rectangle},
+    color::{self, Colorable},
+    widget::{
+        line::Style as LineStyle, Button, Canvas, DropDownList, List, Rectangle, Text, Toggle,
+    },
     Borderable, Labelable, Positionable, Sizeable, UiCell, Widget,
 };
 
@@ -31,7 +33,12 @@ pub fn set_widgets(
 
     let board_width = vc.cell_size * f64::from(cols);
     let indicator_width = vc.cell_size + vc.indicator_text_width;
-    let width = board_width + vc.board_margin * 2.0 + indicator_width + vc.board_margin;
+    let width = board_width
+        + vc.board_margin * 2.0
+        + indicator_width
+        + vc.board_margin
+        + vc.move_list_width
+        + vc.board_margin;
 
     let board_height = vc.cell_size * f64::from(rows);
     let indicator_height = vc.cell_size * 2.0;
@@ -53,10 +60,14 @@ pub fn set_widgets(
         .black_color(vc.black_color)
         .radius_ratio(vc.disk_radius_ratio)
         .dot_radius(vc.dot_radius)
+        .blocked_color(vc.blocked_color)
+        .show_stable(play.show_stable())
         .set(ids.board, ui);
 
     if let Some(pt) = disk_clicked {
-        if play.is_waiting_user_input() {
+        if play.is_placing_obstacles() {
+            play.toggle_obstacle(pt);
+        } else if play.is_waiting_user_input() {
             let _ = play.make_move(pt);
         }
     }
@@ -74,6 +85,7 @@ pub fn set_widgets(
                     build.down_from(ids.black_indicator, 10.0)
                 }
             })
+            .rules(play.board().rules())
             .w(vc.indicator_width)
             .background_color(vc.board_color)
             .border(vc.border_width)
@@ -86,6 +98,34 @@ pub fn set_widgets(
             .set(id, ui);
     }
 
+    let mut lines = vec![];
+    if play.is_placing_obstacles() {
+        lines.push("Click cells to block them".to_string());
+    } else if let Some(side) = play.last_pass() {
+        lines.push(format!("{:?} passes", side));
+    } else if play.board().turn().is_none() {
+        let result = match play.board().winner() {
+            Some(side) => format!("{:?} wins", side),
+            None => "Draw".to_string(),
+        };
+        lines.push(match play.board().rules() {
+            Rules::Standard => result,
+            Rules::Misere => format!("{} (mis\u{e8}re, fewer disks)", result),
+        });
+    }
+    lines.push(format!("seed {}", play.record().seed));
+    lines.extend([Side::Black, Side::White].iter().filter_map(|&side| {
+        play.summary(side)
+            .map(|summary| format!("{:?} AI {}", side, summary))
+    }));
+    lines.extend(play.ai_errors().iter().cloned());
+    lines.extend(play.record_status().map(str::to_string));
+    Text::new(&lines.join("\n"))
+        .w(vc.indicator_width)
+        .font_size(14)
+        .down_from(ids.white_indicator, 10.0)
+        .set(ids.info_text, ui);
+
     let stop_clicked = Button::new()
         .w_h(vc.indicator_width, 50.0)
         .align_left_of(ids.black_indicator)
@@ -100,9 +140,157 @@ pub fn set_widgets(
         .set(ids.stop_button, ui)
         .was_clicked();
 
+    let show_stable = Toggle::new(play.show_stable())
+        .w_h(vc.indicator_width, 50.0)
+        .align_left_of(ids.black_indicator)
+        .up_from(ids.stop_button, 70.0)
+        .label("stable disks")
+        .set(ids.stable_toggle, ui)
+        .last();
+    if let Some(show) = show_stable {
+        play.set_show_stable(show);
+    }
+
+    if play.is_placing_obstacles() {
+        let begin_clicked = Button::new()
+            .w_h(vc.indicator_width, 50.0)
+            .align_left_of(ids.black_indicator)
+            .up_from(ids.stop_button, 10.0)
+            .label("begin")
+            .set(ids.begin_button, ui)
+            .was_clicked();
+        if begin_clicked {
+            play.finish_obstacles();
+        }
+    } else if play.board().turn().is_none() {
+        let save_clicked = Button::new()
+            .w_h(vc.indicator_width, 50.0)
+            .align_left_of(ids.black_indicator)
+            .up_from(ids.stop_button, 10.0)
+            .label("save record")
+            .set(ids.save_record_button, ui)
+            .was_clicked();
+        if save_clicked {
+            play.save_record();
+        }
+    }
+
+    set_move_list(ui, ids, vc, play, board_height);
+
     if stop_clicked {
         Some(State::Start)
     } else {
         None
     }
 }
+
+// The moves of the game with their analysis, and the controls starting it
+// once the game is over.
+fn set_move_list(
+    ui: &mut UiCell<'_>,
+    ids: &mut Ids,
+    vc: &ViewConfig,
+    play: &mut PlayState,
+    board_height: f64,
+) {
+    const CONTROL_HEIGHT: f64 = 50.0;
+    const GAP: f64 = 10.0;
+
+    let is_over = play.board().turn().is_none();
+    let list_height = if is_over {
+        board_height - CONTROL_HEIGHT - GAP
+    } else {
+        board_height
+    };
+    let moves = &play.record().moves;
+    let analyses = play.analyses();
+    // The analyses come one per disk placed, passes left out.
+    let analysis_indices = moves
+        .iter()
+        .scan(0, |num_placed, &mv| {
+            Some(match mv {
+                Move::Place(_) => {
+                    *num_placed += 1;
+                    Some(*num_placed - 1)
+                }
+                Move::Pass => None,
+            })
+        })
+        .collect::<Vec<_>>();
+    let (mut items, scrollbar) = List::flow_down(moves.len())
+        .item_size(20.0)
+        .scrollbar_on_top()
+        .w_h(vc.move_list_width, list_height)
+        .right_from(ids.black_indicator, vc.board_margin)
+        .align_top_of(ids.board)
+        .set(ids.move_list, ui);
+    while let Some(item) = items.next(ui) {
+        let analysis = analysis_indices[item.i].and_then(|idx| analyses.get(idx));
+        let (label, color) = match analysis {
+            Some(analysis) => {
+                let mut label = format!(
+                    "{:>2}. {:?} {}{}",
+                    item.i + 1,
+                    analysis.side,
+                    format_point(analysis.pt),
+                    analysis.grade.symbol()
+                );
+                if analysis.grade != Grade::Best {
+                    label += &format!(
+                        "  best {} ({:.2})",
+                        format_point(analysis.best),
+                        -analysis.loss()
+                    );
+                }
+                let color = match analysis.grade {
+                    Grade::Blunder => color::RED,
+                    Grade::Inaccuracy => color::YELLOW,
+                    Grade::Best | Grade::Good => color::BLACK,
+                };
+                (label, color)
+            }
+            None => (
+                format!("{:>2}. {}", item.i + 1, format_move(moves[item.i])),
+                color::BLACK,
+            ),
+        };
+        item.set(Text::new(&label).font_size(14).color(color), ui);
+    }
+    if let Some(scrollbar) = scrollbar {
+        scrollbar.set(ui);
+    }
+
+    if !is_over {
+        return;
+    }
+    let depths = (1..=12).collect::<Vec<u32>>();
+    let items = depths
+        .iter()
+        .map(|depth| format!("depth {}", depth))
+        .collect::<Vec<_>>();
+    let selected = depths.iter().position(|&d| d == play.analysis_depth());
+    if let Some(idx) = DropDownList::new(&items, selected)
+        .w_h((vc.move_list_width - GAP) / 2.0, CONTROL_HEIGHT)
+        .align_left_of(ids.move_list)
+        .align_bottom_of(ids.board)
+        .max_visible_items(6)
+        .set(ids.analysis_depth_ddl, ui)
+    {
+        play.set_analysis_depth(depths[idx]);
+    }
+    let label = if play.is_analyzing() {
+        "analyzing..."
+    } else {
+        "analyze"
+    };
+    let analyze_clicked = Button::new()
+        .w_h((vc.move_list_width - GAP) / 2.0, CONTROL_HEIGHT)
+        .align_right_of(ids.move_list)
+        .align_bottom_of(ids.board)
+        .label(label)
+        .set(ids.analyze_button, ui)
+        .was_clicked();
+    if analyze_clicked && !play.is_analyzing() {
+        play.start_analysis();
+    }
+}
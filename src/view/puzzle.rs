@@ -0,0 +1,124 @@
+use super::{widget::OthelloBoard, Ids};
+use crate::{
+    model::format_point,
+    view_model::{PuzzleState, PuzzleStats, State, ViewConfig},
+};
+use conrod_core::{
+    color::Colorable,
+    widget::{line::Style as LineStyle, Button, Canvas, Rectangle, Text},
+    Borderable, Labelable, Positionable, Sizeable, UiCell, Widget,
+};
+
+pub fn set_widgets(
+    ui: &mut UiCell<'_>,
+    ids: &mut Ids,
+    vc: &ViewConfig,
+    puzzle: &mut PuzzleState,
+    stats: &mut PuzzleStats,
+) -> Option<State> {
+    puzzle.update(stats);
+
+    let size = puzzle.board().size();
+
+    Canvas::new()
+        .color(vc.board_color)
+        .scroll_kids()
+        .set(ids.canvas, ui);
+
+    let board_width = vc.cell_size * f64::from(size.0);
+    let board_height = vc.cell_size * f64::from(size.1);
+    let width = board_width + vc.board_margin * 3.0 + vc.indicator_width;
+    let height = board_height + vc.board_margin * 2.0;
+
+    let style = LineStyle::new().thickness(0.0);
+    Rectangle::outline_styled([width, height], style)
+        .middle_of(ids.canvas)
+        .set(ids.play_canvas, ui);
+
+    let line = puzzle
+        .solution()
+        .map_or(&[][..], |solution| &solution.line[..]);
+    let board = *puzzle.board();
+    let disk_clicked = OthelloBoard::new(&board, puzzle.answer().is_none())
+        .solution(line)
+        .top_left_with_margins_on(ids.play_canvas, vc.board_margin, vc.board_margin)
+        .w_h(board_width, board_height)
+        .background_color(vc.board_color)
+        .border(vc.border_width)
+        .border_color(vc.border_color)
+        .white_color(vc.white_color)
+        .black_color(vc.black_color)
+        .radius_ratio(vc.disk_radius_ratio)
+        .dot_radius(vc.dot_radius)
+        .set(ids.board, ui);
+    if let Some(pt) = disk_clicked {
+        puzzle.answer_with(pt);
+    }
+
+    let mut lines = vec![format!(
+        "Puzzle {} of {}",
+        puzzle.index() + 1,
+        puzzle.num_puzzles()
+    )];
+    if let Some(turn) = board.turn() {
+        lines.push(format!("{:?} to move. Find the best move!", turn));
+    }
+    if let Some(answer) = puzzle.answer() {
+        lines.push(String::new());
+        let verdict = match puzzle.is_correct() {
+            None => "checking...",
+            Some(true) => "correct!",
+            Some(false) => "wrong.",
+        };
+        lines.push(format!("You played {}: {}", format_point(answer), verdict));
+    }
+    if let Some(solution) = puzzle.solution() {
+        lines.push(format!("{}.", solution));
+        lines.push("The numbers show a perfect line.".to_string());
+        let comment = &puzzle.puzzle().comment;
+        if !comment.is_empty() && *comment != solution.to_string() {
+            lines.push(format!("({})", comment));
+        }
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "Solved {} of {} this session",
+        stats.num_solved, stats.num_answered
+    ));
+    if let Some(e) = puzzle.load_error() {
+        lines.push(String::new());
+        lines.push(e.to_string());
+    }
+    Text::new(&lines.join("\n"))
+        .w(vc.indicator_width)
+        .font_size(16)
+        .wrap_by_word()
+        .right_from(ids.board, vc.board_margin)
+        .align_top_of(ids.board)
+        .set(ids.puzzle_text, ui);
+
+    let return_clicked = Button::new()
+        .w_h(vc.indicator_width, 50.0)
+        .right_from(ids.board, vc.board_margin)
+        .align_bottom_of(ids.board)
+        .label("return")
+        .set(ids.stop_button, ui)
+        .was_clicked();
+
+    let next_clicked = Button::new()
+        .w_h(vc.indicator_width, 50.0)
+        .right_from(ids.board, vc.board_margin)
+        .up_from(ids.stop_button, 10.0)
+        .label("next puzzle")
+        .set(ids.next_puzzle_button, ui)
+        .was_clicked();
+    if next_clicked {
+        puzzle.next_puzzle();
+    }
+
+    if return_clicked {
+        Some(State::Start)
+    } else {
+        None
+    }
+}
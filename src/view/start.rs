@@ -1,19 +1,34 @@
 use super::Ids;
 use crate::{
-    model::{PlayerKind, Size},
-    view_model::{BoardSize, GameConfig, PlayState, State, ViewConfig},
+    model::{
+        AiConfig, Algorithm, EvaluatorKind, PlayerKind, Rules, Side, Size, StartPosition, Strength,
+        Topology,
+    },
+    view_model::{BoardSize, GameConfig, Obstacles, PlayState, PuzzleState, State, ViewConfig},
 };
 use conrod_core::{
     color::Colorable,
-    widget::{Button, Canvas, DropDownList, Text},
+    widget::{self, Button, Canvas, DropDownList, Text, Toggle},
     Labelable, Positionable, Sizeable, UiCell, Widget,
 };
 
+// Choices of random opening moves on the start screen.
+const OPENING_PLIES: [u32; 5] = [0, 2, 4, 6, 8];
+// Choices of blocked cells on the start screen.
+const OBSTACLES: [Obstacles; 4] = [
+    Obstacles::Random(0),
+    Obstacles::Random(4),
+    Obstacles::Random(8),
+    Obstacles::HandPlaced,
+];
+
 pub fn set_widgets(
     ui: &mut UiCell<'_>,
     ids: &mut Ids,
     gc: &mut GameConfig,
     vc: &ViewConfig,
+    max_threads: usize,
+    show_ai_settings: &mut bool,
 ) -> Option<State> {
     Canvas::new()
         .color(vc.board_color)
@@ -43,40 +58,383 @@ pub fn set_widgets(
         .map(|idx| board_sizes[idx])
         .unwrap_or(gc.cols);
 
-    let player_kinds = PlayerKind::all_values();
-    gc.black_player = DropDownList::new(&player_kinds, Some(gc.black_player.to_index()))
+    // The pattern evaluator and its presets only show for sizes it has
+    // weights for.
+    let size = Size(gc.cols.to_value(), gc.rows.to_value());
+    for kind in [&mut gc.black_player, &mut gc.white_player] {
+        if let PlayerKind::Ai(config) = kind {
+            if !config.evaluator.is_available(size) {
+                config.evaluator = EvaluatorKind::Strong;
+            }
+        }
+    }
+    let presets = AiConfig::presets()
+        .into_iter()
+        .filter(|preset| preset.config.evaluator.is_available(size))
+        .collect::<Vec<_>>();
+    // "Human", the presets, and "custom" for AIs matching no preset
+    let player_items = Some("Human")
+        .into_iter()
+        .chain(presets.iter().map(|preset| preset.name))
+        .chain(Some("custom"))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let player_index = |kind: PlayerKind| match kind {
+        PlayerKind::Human => 0,
+        PlayerKind::Ai(config) => presets
+            .iter()
+            .position(|preset| Some(preset.name) == config.preset_name())
+            .map_or(presets.len() + 1, |idx| idx + 1),
+    };
+    // Threads and pondering are kept when switching presets.
+    let select_player = |kind: PlayerKind, idx: usize| match (kind, idx) {
+        (_, 0) => PlayerKind::Human,
+        (kind, idx) if idx > presets.len() => match kind {
+            PlayerKind::Human => PlayerKind::Ai(presets[0].config),
+            kind => kind,
+        },
+        (PlayerKind::Ai(config), idx) => PlayerKind::Ai(AiConfig {
+            num_threads: config.num_threads,
+            ponder: config.ponder,
+            ..presets[idx - 1].config
+        }),
+        (PlayerKind::Human, idx) => PlayerKind::Ai(presets[idx - 1].config),
+    };
+
+    gc.black_player = DropDownList::new(&player_items, Some(player_index(gc.black_player)))
         .w_h(300.0, 50.0)
         .down_from(ids.times_label, 40.0)
         .left_from(ids.times_label, 30.0)
         .label("Black Player")
         .set(ids.black_player_ddl, ui)
-        .map(|idx| player_kinds[idx])
+        .map(|idx| select_player(gc.black_player, idx))
         .unwrap_or(gc.black_player);
-    gc.white_player = DropDownList::new(&player_kinds, Some(gc.white_player.to_index()))
+    gc.white_player = DropDownList::new(&player_items, Some(player_index(gc.white_player)))
         .w_h(300.0, 50.0)
         .down_from(ids.times_label, 40.0)
         .right_from(ids.times_label, 30.0)
         .label("White Player")
         .set(ids.white_player_ddl, ui)
-        .map(|idx| player_kinds[idx])
+        .map(|idx| select_player(gc.white_player, idx))
         .unwrap_or(gc.white_player);
 
-    let start_clicked = Button::new()
+    let starts = StartPosition::all_values();
+    let items = starts
+        .iter()
+        .map(|s| format!("start: {}", s.as_ref()))
+        .collect::<Vec<_>>();
+    gc.start = DropDownList::new(&items, Some(gc.start.to_index()))
         .w_h(200.0, 50.0)
         .down_from(ids.times_label, 130.0)
         .align_middle_x_of(ids.times_label)
+        .set(ids.start_ddl, ui)
+        .map(|idx| starts[idx])
+        .unwrap_or(gc.start);
+    let sides = Side::all_values();
+    let items = sides
+        .iter()
+        .map(|s| format!("first: {}", s.as_ref()))
+        .collect::<Vec<_>>();
+    gc.first = DropDownList::new(&items, Some(gc.first.to_index()))
+        .w_h(200.0, 50.0)
+        .left_from(ids.start_ddl, 20.0)
+        .set(ids.first_ddl, ui)
+        .map(|idx| sides[idx])
+        .unwrap_or(gc.first);
+    let items = OPENING_PLIES
+        .iter()
+        .map(|&n| match n {
+            0 => "opening: as is".to_string(),
+            n => format!("opening: {} random", n),
+        })
+        .collect::<Vec<_>>();
+    let selected = OPENING_PLIES.iter().position(|&n| n == gc.opening_plies);
+    gc.opening_plies = DropDownList::new(&items, selected)
+        .w_h(200.0, 50.0)
+        .right_from(ids.start_ddl, 20.0)
+        .set(ids.opening_ddl, ui)
+        .map(|idx| OPENING_PLIES[idx])
+        .unwrap_or(gc.opening_plies);
+    let all_rules = Rules::all_values();
+    let items = all_rules
+        .iter()
+        .map(|r| format!("rules: {}", r.as_ref()))
+        .collect::<Vec<_>>();
+    gc.rules = DropDownList::new(&items, Some(gc.rules.to_index()))
+        .w_h(200.0, 50.0)
+        .down_from(ids.start_ddl, 10.0)
+        .set(ids.rules_ddl, ui)
+        .map(|idx| all_rules[idx])
+        .unwrap_or(gc.rules);
+    let topologies = Topology::all_values();
+    let items = topologies
+        .iter()
+        .map(|t| format!("topology: {}", t.as_ref()))
+        .collect::<Vec<_>>();
+    gc.topology = DropDownList::new(&items, Some(gc.topology.to_index()))
+        .w_h(200.0, 50.0)
+        .left_from(ids.rules_ddl, 20.0)
+        .set(ids.topology_ddl, ui)
+        .map(|idx| topologies[idx])
+        .unwrap_or(gc.topology);
+    let items = OBSTACLES
+        .iter()
+        .map(|&o| match o {
+            Obstacles::Random(0) => "obstacles: none".to_string(),
+            Obstacles::Random(n) => format!("obstacles: {} random", n),
+            Obstacles::HandPlaced => "obstacles: by hand".to_string(),
+        })
+        .collect::<Vec<_>>();
+    let selected = OBSTACLES.iter().position(|&o| o == gc.obstacles);
+    gc.obstacles = DropDownList::new(&items, selected)
+        .w_h(200.0, 50.0)
+        .right_from(ids.rules_ddl, 20.0)
+        .set(ids.obstacles_ddl, ui)
+        .map(|idx| OBSTACLES[idx])
+        .unwrap_or(gc.obstacles);
+
+    let settings_label = if *show_ai_settings {
+        "AI settings \u{25b2}"
+    } else {
+        "AI settings \u{25bc}"
+    };
+    *show_ai_settings = Toggle::new(*show_ai_settings)
+        .w_h(200.0, 50.0)
+        .down_from(ids.times_label, 280.0)
+        .align_middle_x_of(ids.times_label)
+        .label(settings_label)
+        .set(ids.ai_settings_toggle, ui)
+        .last()
+        .unwrap_or(*show_ai_settings);
+
+    let mut settings_height = 0.0;
+    if *show_ai_settings {
+        let columns = vec![
+            (ids.black_player_ddl, &mut gc.black_player),
+            (ids.white_player_ddl, &mut gc.white_player),
+        ];
+        for (column, (top, kind)) in columns.into_iter().enumerate() {
+            if let PlayerKind::Ai(config) = kind {
+                let height = set_ai_settings(ui, ids, column, top, config, size, max_threads);
+                settings_height = f64::max(settings_height, height);
+            }
+        }
+    }
+
+    let start_clicked = Button::new()
+        .w_h(200.0, 50.0)
+        .down_from(ids.times_label, 370.0 + settings_height)
+        .align_middle_x_of(ids.times_label)
         .label("start")
         .set(ids.start_button, ui)
         .was_clicked();
 
+    let puzzle_clicked = Button::new()
+        .w_h(200.0, 50.0)
+        .down_from(ids.start_button, 10.0)
+        .align_middle_x_of(ids.times_label)
+        .label("puzzles")
+        .set(ids.puzzle_button, ui)
+        .was_clicked();
+    if puzzle_clicked {
+        return Some(State::Puzzle(Box::new(PuzzleState::new())));
+    }
+
     if start_clicked {
-        let new_state = State::Play(Box::new(PlayState::new(
-            Size(gc.cols.to_value(), gc.rows.to_value()),
-            gc.black_player,
-            gc.white_player,
-        )));
+        let new_state = State::Play(Box::new(PlayState::new(gc)));
         Some(new_state)
     } else {
         None
     }
 }
+
+// Sets the widgets editing `config` in a column below `top`, and returns the
+// height they take below the settings toggle.
+fn set_ai_settings(
+    ui: &mut UiCell<'_>,
+    ids: &mut Ids,
+    column: usize,
+    top: widget::Id,
+    config: &mut AiConfig,
+    size: Size,
+    max_threads: usize,
+) -> f64 {
+    const ITEM_HEIGHT: f64 = 40.0;
+    const GAP: f64 = 10.0;
+    // from the bottom of the player list to below the settings toggle
+    const FIRST_GAP: f64 = 260.0;
+
+    {
+        let mut id_gen = ui.widget_id_generator();
+        let lists = [
+            &mut ids.algorithm_ddls,
+            &mut ids.evaluator_ddls,
+            &mut ids.budget_kind_ddls,
+            &mut ids.budget_ddls,
+            &mut ids.strength_ddls,
+            &mut ids.book_toggles,
+            &mut ids.threads_ddls,
+            &mut ids.ponder_toggles,
+        ];
+        for list in lists {
+            if list.len() < 2 {
+                list.resize(2, &mut id_gen);
+            }
+        }
+    }
+
+    // Each widget goes below the previous one.
+    let mut prev = top;
+    let mut gap = FIRST_GAP;
+    let mut height = 0.0;
+    let mut place = |id: widget::Id| {
+        let pos = (prev, gap);
+        height += gap + ITEM_HEIGHT;
+        prev = id;
+        gap = GAP;
+        pos
+    };
+
+    let algorithms = Algorithm::all_values();
+    let items = algorithms
+        .iter()
+        .map(|a| format!("algorithm: {}", a.as_ref()))
+        .collect::<Vec<_>>();
+    let id = ids.algorithm_ddls[column];
+    let (below, gap) = place(id);
+    if let Some(idx) = DropDownList::new(&items, Some(config.algorithm.to_index()))
+        .w_h(300.0, ITEM_HEIGHT)
+        .down_from(below, gap)
+        .set(id, ui)
+    {
+        let algorithm = algorithms[idx];
+        if !algorithm.budget_kinds().contains(&config.budget.kind()) {
+            if let Some(kind) = algorithm.budget_kinds().first() {
+                config.budget = kind.default_budget();
+            }
+        }
+        config.algorithm = algorithm;
+    }
+
+    if config.algorithm == Algorithm::AlphaBeta {
+        let evaluators = EvaluatorKind::all_values()
+            .iter()
+            .copied()
+            .filter(|e| e.is_available(size))
+            .collect::<Vec<_>>();
+        let items = evaluators
+            .iter()
+            .map(|e| format!("evaluator: {}", e.as_ref()))
+            .collect::<Vec<_>>();
+        let selected = evaluators.iter().position(|&e| e == config.evaluator);
+        let id = ids.evaluator_ddls[column];
+        let (below, gap) = place(id);
+        config.evaluator = DropDownList::new(&items, selected)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .set(id, ui)
+            .map(|idx| evaluators[idx])
+            .unwrap_or(config.evaluator);
+    }
+
+    let budget_kinds = config.algorithm.budget_kinds();
+    if !budget_kinds.is_empty() {
+        let items = budget_kinds
+            .iter()
+            .map(|k| format!("budget: {}", k.as_ref()))
+            .collect::<Vec<_>>();
+        let selected = budget_kinds.iter().position(|&k| k == config.budget.kind());
+        let id = ids.budget_kind_ddls[column];
+        let (below, gap) = place(id);
+        if let Some(idx) = DropDownList::new(&items, selected)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .set(id, ui)
+        {
+            if budget_kinds[idx] != config.budget.kind() {
+                config.budget = budget_kinds[idx].default_budget();
+            }
+        }
+
+        let budgets = config.budget.kind().choices();
+        let items = budgets.iter().map(|b| b.to_string()).collect::<Vec<_>>();
+        let selected = budgets.iter().position(|&b| b == config.budget);
+        let id = ids.budget_ddls[column];
+        let (below, gap) = place(id);
+        config.budget = DropDownList::new(&items, selected)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .label(&config.budget.to_string())
+            .max_visible_items(8)
+            .set(id, ui)
+            .map(|idx| budgets[idx])
+            .unwrap_or(config.budget);
+    }
+
+    if config.algorithm != Algorithm::Random {
+        let strengths = Strength::choices();
+        let items = strengths
+            .iter()
+            .map(|s| format!("strength: {}", s))
+            .collect::<Vec<_>>();
+        let selected = strengths.iter().position(|&s| s == config.strength);
+        let id = ids.strength_ddls[column];
+        let (below, gap) = place(id);
+        config.strength = DropDownList::new(&items, selected)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .label(&format!("strength: {}", config.strength))
+            .max_visible_items(8)
+            .set(id, ui)
+            .map(|idx| strengths[idx])
+            .unwrap_or(config.strength);
+
+        let label = if config.book {
+            "opening book: on"
+        } else {
+            "opening book: off"
+        };
+        let id = ids.book_toggles[column];
+        let (below, gap) = place(id);
+        config.book = Toggle::new(config.book)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .label(label)
+            .set(id, ui)
+            .last()
+            .unwrap_or(config.book);
+    }
+
+    if config.algorithm == Algorithm::AlphaBeta {
+        let items = (1..=max_threads)
+            .map(|n| format!("threads: {}", n))
+            .collect::<Vec<_>>();
+        let id = ids.threads_ddls[column];
+        let (below, gap) = place(id);
+        config.num_threads =
+            DropDownList::new(&items, Some(config.num_threads.clamp(1, items.len()) - 1))
+                .w_h(300.0, ITEM_HEIGHT)
+                .down_from(below, gap)
+                .max_visible_items(8)
+                .set(id, ui)
+                .map(|idx| idx + 1)
+                .unwrap_or(config.num_threads);
+
+        let label = if config.ponder {
+            "pondering: on"
+        } else {
+            "pondering: off"
+        };
+        let id = ids.ponder_toggles[column];
+        let (below, gap) = place(id);
+        config.ponder = Toggle::new(config.ponder)
+            .w_h(300.0, ITEM_HEIGHT)
+            .down_from(below, gap)
+            .label(label)
+            .set(id, ui)
+            .last()
+            .unwrap_or(config.ponder);
+    }
+
+    height - FIRST_GAP + 2.0 * GAP
+}
@@ -1,5 +1,5 @@
 use super::OthelloDisk;
-use crate::model::{PlayerKind, Side};
+use crate::model::{PlayerKind, Rules, Side};
 use conrod_core::{
     builder_methods,
     color::{self, Color, Colorable},
@@ -15,6 +15,7 @@ pub struct Indicator {
     side: Side,
     kind: PlayerKind,
     num_disk: u32,
+    rules: Rules,
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
@@ -62,10 +63,20 @@ impl<'a> Indicator {
             side,
             kind,
             num_disk,
+            rules: Rules::Standard,
+        }
+    }
+
+    // The player name, with the rules unless they are the standard ones.
+    fn title(&self) -> String {
+        match self.rules {
+            Rules::Standard => self.kind.name(),
+            Rules::Misere => format!("{} (mis\u{e8}re)", self.kind.name()),
         }
     }
 
     builder_methods! {
+        pub rules { rules = Rules }
         pub player_name_font_size { style.player_name_font_size = Some(FontSize) }
         pub count_font_size { style.count_font_size = Some(FontSize) }
         pub white_color { style.white_color = Some(Color) }
@@ -120,7 +131,7 @@ impl Widget for Indicator {
             .border_color(style.border_color(ui.theme()))
             .set(state.ids.rectangle, &mut ui);
 
-        Text::new(self.kind.as_ref())
+        Text::new(&self.title())
             .top_left_with_margin_on(id, 5.0)
             .w(dim[0])
             .font_size(style.player_name_font_size(ui.theme()))
@@ -146,7 +157,7 @@ impl Widget for Indicator {
     }
 
     fn default_y_dimension(&self, ui: &Ui) -> Dimension {
-        let name_height = Text::new(self.kind.as_ref())
+        let name_height = Text::new(&self.title())
             .w(self.get_w(ui).unwrap_or(0.0))
             .font_size(self.style.player_name_font_size(&ui.theme))
             .get_h(ui)
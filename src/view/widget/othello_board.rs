@@ -1,9 +1,9 @@
 use super::OthelloDisk;
-use crate::model::{Board, Point};
+use crate::model::{BitBoard, Board, Point};
 use conrod_core::{
     builder_methods,
     color::{self, Color, Colorable},
-    widget::{self, Circle, Common, CommonBuilder, Matrix, UpdateArgs},
+    widget::{self, Circle, Common, CommonBuilder, Matrix, Text, UpdateArgs},
     widget_ids, Borderable, Positionable, Scalar, Sizeable, Widget, WidgetStyle,
 };
 
@@ -13,6 +13,8 @@ pub struct OthelloBoard<'a> {
     style: Style,
     board: &'a Board,
     show_candidates: bool,
+    show_stable: bool,
+    solution: &'a [Point],
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
@@ -31,6 +33,12 @@ pub struct Style {
     pub radius_ratio: Option<Scalar>,
     #[conrod(default = "6.0")]
     pub dot_radius: Option<Scalar>,
+    #[conrod(default = "color::RED")]
+    pub solution_color: Option<Color>,
+    #[conrod(default = "color::DARK_CHARCOAL")]
+    pub blocked_color: Option<Color>,
+    #[conrod(default = "color::LIGHT_RED")]
+    pub stable_color: Option<Color>,
 }
 
 widget_ids! {
@@ -41,6 +49,8 @@ widget_ids! {
         dot_ur,
         dot_dl,
         dot_dr,
+        solution_labels[],
+        stable_marks[],
     }
 }
 
@@ -56,15 +66,27 @@ impl<'a> OthelloBoard<'a> {
             style: Style::default(),
             board,
             show_candidates,
+            show_stable: false,
+            solution: &[],
         }
     }
 
+    /// Numbers the cells of the moves of `line`, played from the board.
+    pub fn solution(mut self, line: &'a [Point]) -> Self {
+        self.solution = line;
+        self
+    }
+
     builder_methods! {
         pub white_color { style.white_color = Some(Color) }
         pub black_color { style.black_color = Some(Color) }
         pub background_color { style.background_color = Some(Color) }
         pub radius_ratio { style.radius_ratio = Some(Scalar) }
         pub dot_radius { style.dot_radius = Some(Scalar) }
+        pub solution_color { style.solution_color = Some(Color) }
+        pub blocked_color { style.blocked_color = Some(Color) }
+        pub stable_color { style.stable_color = Some(Color) }
+        pub show_stable { show_stable = bool }
     }
 }
 
@@ -112,6 +134,7 @@ impl<'a> Widget for OthelloBoard<'a> {
 
         let mut result = None;
         let cands = self.board.move_candidates();
+        let blocked = self.board.blocked_cells();
         while let Some(element) = elements.next(ui) {
             let pt = Point(element.col as u32, element.row as u32);
             let mut disk = OthelloDisk::new();
@@ -123,8 +146,14 @@ impl<'a> Widget for OthelloBoard<'a> {
             if let Some(side) = self.board.get(pt) {
                 disk = disk.disk(side);
             }
+            // Blocked cells are filled in, as they never take a disk.
+            let background_color = if blocked.contains(pt, size) {
+                style.blocked_color(ui.theme())
+            } else {
+                style.background_color(ui.theme())
+            };
             disk = disk
-                .background_color(style.background_color(ui.theme()))
+                .background_color(background_color)
                 .border(style.border(ui.theme()))
                 .border_color(style.border_color(ui.theme()))
                 .white_color(style.white_color(ui.theme()))
@@ -137,9 +166,54 @@ impl<'a> Widget for OthelloBoard<'a> {
             }
         }
 
+        let cell_width = w / f64::from(size.0);
+        let cell_height = h / f64::from(size.1);
+        if state.ids.solution_labels.len() < self.solution.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state
+                    .ids
+                    .solution_labels
+                    .resize(self.solution.len(), id_gen)
+            });
+        }
+        // A dot on each disk that can no longer flip.
+        let stable = if self.show_stable {
+            self.board.stable_disks()
+        } else {
+            BitBoard::empty()
+        };
+        let num_stable = stable.num_bits() as usize;
+        if state.ids.stable_marks.len() < num_stable {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.ids.stable_marks.resize(num_stable, id_gen));
+        }
+        for (i, pt) in stable.points(size).enumerate() {
+            Circle::fill(style.dot_radius(ui.theme()))
+                .x_y_relative_to(
+                    state.ids.matrix,
+                    -w / 2.0 + cell_width * (f64::from(pt.0) + 0.5),
+                    h / 2.0 - cell_height * (f64::from(pt.1) + 0.5),
+                )
+                .color(style.stable_color(ui.theme()))
+                .graphics_for(id)
+                .set(state.ids.stable_marks[i], ui);
+        }
+
+        let font_size = (f64::min(cell_width, cell_height) * 0.4) as u32;
+        for (i, &pt) in self.solution.iter().enumerate() {
+            Text::new(&(i + 1).to_string())
+                .x_y_relative_to(
+                    state.ids.matrix,
+                    -w / 2.0 + cell_width * (f64::from(pt.0) + 0.5),
+                    h / 2.0 - cell_height * (f64::from(pt.1) + 0.5),
+                )
+                .font_size(font_size)
+                .color(style.solution_color(ui.theme()))
+                .set(state.ids.solution_labels[i], ui);
+        }
+
         if size.0 >= 4 && size.1 >= 4 {
-            let cell_width = w / f64::from(size.0);
-            let cell_height = h / f64::from(size.1);
             let (sx, sy) = (f64::from(size.0), f64::from(size.1));
             let pairs = &[
                 (state.ids.dot_ul, (2.0, 2.0)),
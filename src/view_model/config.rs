@@ -1,11 +1,18 @@
 use super::BoardSize;
-use crate::model::PlayerKind;
+use crate::model::{PlayerKind, Rules, Side, StartPosition, Topology};
 use conrod_core::color::{self, Color};
 
 #[derive(Copy, Clone, Debug)]
 pub struct GameConfig {
     pub rows: BoardSize,
     pub cols: BoardSize,
+    pub start: StartPosition,
+    pub first: Side,
+    pub rules: Rules,
+    pub topology: Topology,
+    pub obstacles: Obstacles,
+    /// Random moves, kept near even, before the players take over.
+    pub opening_plies: u32,
     pub black_player: PlayerKind,
     pub white_player: PlayerKind,
 }
@@ -15,12 +22,27 @@ impl Default for GameConfig {
         GameConfig {
             rows: BoardSize::N8,
             cols: BoardSize::N8,
+            start: StartPosition::Crossed,
+            first: Side::Black,
+            rules: Rules::Standard,
+            topology: Topology::Planar,
+            obstacles: Obstacles::Random(0),
+            opening_plies: 0,
             black_player: PlayerKind::Human,
             white_player: PlayerKind::Human,
         }
     }
 }
 
+/// Cells blocked for the whole game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Obstacles {
+    /// About this many random cells, in pairs opposite through the centre.
+    Random(u32),
+    /// Cells clicked on the board before the game begins.
+    HandPlaced,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct ViewConfig {
     pub border_width: f64,
@@ -30,11 +52,13 @@ pub struct ViewConfig {
     pub board_margin: f64,
     pub indicator_text_width: f64,
     pub indicator_width: f64,
+    pub move_list_width: f64,
 
     pub border_color: Color,
     pub board_color: Color,
     pub white_color: Color,
     pub black_color: Color,
+    pub blocked_color: Color,
 }
 
 impl Default for ViewConfig {
@@ -47,11 +71,13 @@ impl Default for ViewConfig {
             board_margin: 40.0,
             indicator_text_width: 90.0,
             indicator_width: 240.0,
+            move_list_width: 240.0,
 
             border_color: color::BLACK,
             board_color: color::rgba(0.0, 0.5, 0.0, 1.0),
             white_color: color::WHITE,
             black_color: color::BLACK,
+            blocked_color: color::DARK_CHARCOAL,
         }
     }
 }
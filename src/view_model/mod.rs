@@ -1,15 +1,22 @@
 pub use self::{
-    config::{GameConfig, ViewConfig},
+    config::{GameConfig, Obstacles, ViewConfig},
+    puzzle::{PuzzleState, PuzzleStats},
     state::{PlayState, State},
 };
 
+use std::thread;
+
 mod config;
+mod puzzle;
 mod state;
 
 pub struct App {
     pub state: State,
     pub game_config: GameConfig,
     pub view_config: ViewConfig,
+    pub max_threads: usize,
+    pub show_ai_settings: bool,
+    pub puzzle_stats: PuzzleStats,
 }
 
 impl Default for App {
@@ -18,6 +25,9 @@ impl Default for App {
             state: State::Start,
             game_config: GameConfig::default(),
             view_config: ViewConfig::default(),
+            max_threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            show_ai_settings: false,
+            puzzle_stats: PuzzleStats::default(),
         }
     }
 }
@@ -31,6 +41,8 @@ pub enum BoardSize {
     N6,
     N7,
     N8,
+    N9,
+    N10,
 }
 
 impl AsRef<str> for BoardSize {
@@ -44,14 +56,16 @@ impl AsRef<str> for BoardSize {
             N6 => "6",
             N7 => "7",
             N8 => "8",
+            N9 => "9",
+            N10 => "10",
         }
     }
 }
 
 impl BoardSize {
-    pub fn all_values() -> [Self; 7] {
+    pub fn all_values() -> [Self; 9] {
         use self::BoardSize::*;
-        [N2, N3, N4, N5, N6, N7, N8]
+        [N2, N3, N4, N5, N6, N7, N8, N9, N10]
     }
 
     pub fn to_index(self) -> usize {
@@ -68,6 +82,8 @@ impl BoardSize {
             N6 => 6,
             N7 => 7,
             N8 => 8,
+            N9 => 9,
+            N10 => 10,
         }
     }
 }
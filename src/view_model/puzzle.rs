@@ -0,0 +1,151 @@
+use crate::model::{self, Board, Point, Puzzle, Solution};
+use std::{
+    env, fs,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+/// Puzzles answered and solved since the program started.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PuzzleStats {
+    pub num_answered: u32,
+    pub num_solved: u32,
+}
+
+pub struct PuzzleState {
+    puzzles: Vec<Puzzle>,
+    index: usize,
+    // why the puzzle file could not be used
+    load_error: Option<String>,
+    answer: Option<Point>,
+    solution: Option<Solution>,
+    // the solution while it is being searched
+    solution_rx: Option<Receiver<Solution>>,
+    counted: bool,
+}
+
+impl PuzzleState {
+    /// Starts with the puzzles of the file `$OTHELLO_PUZZLES`, or the
+    /// built-in ones.
+    pub fn new() -> PuzzleState {
+        let (puzzles, load_error) = match env::var_os("OTHELLO_PUZZLES") {
+            Some(path) => {
+                let loaded = fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| model::parse_puzzles(&text))
+                    .and_then(|puzzles| {
+                        if puzzles.is_empty() {
+                            Err("no puzzles".to_string())
+                        } else {
+                            Ok(puzzles)
+                        }
+                    });
+                match loaded {
+                    Ok(puzzles) => (puzzles, None),
+                    Err(e) => (
+                        model::builtin_puzzles(),
+                        Some(format!("cannot load {}: {}", path.to_string_lossy(), e)),
+                    ),
+                }
+            }
+            None => (model::builtin_puzzles(), None),
+        };
+        let mut state = PuzzleState {
+            puzzles,
+            index: 0,
+            load_error,
+            answer: None,
+            solution: None,
+            solution_rx: None,
+            counted: false,
+        };
+        state.start_puzzle(0);
+        state
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn num_puzzles(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.puzzle().board
+    }
+
+    pub fn answer(&self) -> Option<Point> {
+        self.answer
+    }
+
+    /// The solution, once the answer is given and the search has finished.
+    pub fn solution(&self) -> Option<&Solution> {
+        self.answer.and(self.solution.as_ref())
+    }
+
+    /// Whether the answer is one of the best moves, once both are known.
+    pub fn is_correct(&self) -> Option<bool> {
+        let answer = self.answer?;
+        let solution = self.solution.as_ref()?;
+        Some(solution.best_moves.contains(&answer))
+    }
+
+    /// Answers with `pt` if it is a legal move and no answer is given yet.
+    pub fn answer_with(&mut self, pt: Point) {
+        let size = self.board().size();
+        if self.answer.is_none() && self.board().move_candidates().contains(pt, size) {
+            self.answer = Some(pt);
+        }
+    }
+
+    pub fn next_puzzle(&mut self) {
+        self.start_puzzle((self.index + 1) % self.puzzles.len());
+    }
+
+    /// Receives the solution when its search finishes, and counts the answer
+    /// in `stats` once it can be checked.
+    pub fn update(&mut self, stats: &mut PuzzleStats) {
+        if let Some(rx) = &self.solution_rx {
+            match rx.try_recv() {
+                Ok(solution) => {
+                    self.solution = Some(solution);
+                    self.solution_rx = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => panic!("puzzle solver died"),
+            }
+        }
+        if let (false, Some(correct)) = (self.counted, self.is_correct()) {
+            self.counted = true;
+            stats.num_answered += 1;
+            if correct {
+                stats.num_solved += 1;
+            }
+        }
+    }
+
+    // The exact search starts right away, so that the answer can usually be
+    // checked as soon as it is given.
+    fn start_puzzle(&mut self, index: usize) {
+        let (tx, rx) = mpsc::channel();
+        let board = self.puzzles[index].board;
+        let _ = thread::spawn(move || {
+            // The receiver is gone if another puzzle was chosen meanwhile.
+            let _ = tx.send(model::solve(&board).unwrap());
+        });
+        self.index = index;
+        self.answer = None;
+        self.solution = None;
+        self.solution_rx = Some(rx);
+        self.counted = false;
+    }
+}
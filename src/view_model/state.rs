@@ -1,45 +1,169 @@
-use crate::model::{AiPlayer, Board, PlayerKind, Point, Side, Size};
-use std::{mem, sync::mpsc::TryRecvError};
+use super::{GameConfig, Obstacles, PuzzleState};
+use crate::model::{
+    self, AiPlayer, Board, GameRecord, Move, MoveAnalysis, PlayerKind, Point, Side, Size,
+};
+use std::{
+    env, fs, io, mem,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+// Positions with at most this many empty cells are analysed exactly.
+const ANALYSIS_EXACT_EMPTIES: u32 = 10;
+// Random openings are redrawn until a shallow search scores them within this
+// of even.
+const OPENING_BALANCE: f64 = 0.3;
 
 pub enum State {
     Start,
     Play(Box<PlayState>),
+    Puzzle(Box<PuzzleState>),
 }
 
 pub struct PlayState {
     board: Board,
+    record: GameRecord,
+    // while the user clicks the cells to block, before the game begins
+    placing_obstacles: bool,
+    opening_plies: u32,
+    // where the record was saved, or why it could not be
+    record_status: Option<String>,
+    // why the AIs left to the user could not start
+    ai_errors: Vec<String>,
+    analysis_depth: u32,
+    analyses: Vec<MoveAnalysis>,
+    show_stable: bool,
+    // analyses of the remaining moves, while the analysis runs
+    analysis_rx: Option<Receiver<MoveAnalysis>>,
     black_kind: PlayerKind,
     white_kind: PlayerKind,
     black_ai_player: Option<AiPlayer>,
     white_ai_player: Option<AiPlayer>,
+    black_summary: Option<String>,
+    white_summary: Option<String>,
 }
 
 impl PlayState {
-    pub fn new(size: Size, black_kind: PlayerKind, white_kind: PlayerKind) -> PlayState {
-        let board = Board::new(size);
-        PlayState {
-            board,
+    pub fn new(gc: &GameConfig) -> PlayState {
+        let size = Size(gc.cols.to_value(), gc.rows.to_value());
+        let (black_kind, white_kind) = (gc.black_player, gc.white_player);
+        let seed = rand::random();
+        let mut record = GameRecord::new(size, seed, black_kind, white_kind);
+        record.start = gc.start;
+        record.first = gc.first;
+        record.rules = gc.rules;
+        record.topology = gc.topology;
+        if let Obstacles::Random(num) = gc.obstacles {
+            record.blocked =
+                model::random_obstacles(&record.initial_board(), num, &mut rand::thread_rng());
+        }
+        let mut play = PlayState {
+            board: record.initial_board(),
+            record,
+            placing_obstacles: gc.obstacles == Obstacles::HandPlaced,
+            opening_plies: gc.opening_plies,
+            record_status: None,
+            ai_errors: vec![],
+            analysis_depth: 6,
+            analyses: vec![],
+            show_stable: false,
+            analysis_rx: None,
             black_kind,
             white_kind,
-            black_ai_player: AiPlayer::try_new(black_kind, &board, Side::Black),
-            white_ai_player: AiPlayer::try_new(white_kind, &board, Side::White),
+            black_ai_player: None,
+            white_ai_player: None,
+            black_summary: None,
+            white_summary: None,
+        };
+        if !play.placing_obstacles {
+            play.begin();
+        }
+        play
+    }
+
+    // Plays the random opening and lets the players take over.
+    fn begin(&mut self) {
+        self.record.moves = model::random_opening(
+            &self.record.initial_board(),
+            self.opening_plies,
+            Some(OPENING_BALANCE),
+            &mut rand::thread_rng(),
+        );
+        self.record.opening = self.record.moves.len();
+        let board = self.record.start_board().unwrap();
+        self.board = board;
+        self.black_ai_player = self.start_ai(Side::Black, &board);
+        self.white_ai_player = self.start_ai(Side::White, &board);
+    }
+
+    // Starts the AI of `side`, or leaves the side to the user if it cannot
+    // start, recording it as human and telling why.
+    fn start_ai(&mut self, side: Side, board: &Board) -> Option<AiPlayer> {
+        let kind = self.player_kind(side);
+        match AiPlayer::try_new(kind, board, side, self.record.seed) {
+            Ok(player) => player,
+            Err(e) => {
+                self.ai_errors
+                    .push(format!("{:?} AI cannot start: {}", side, e));
+                match side {
+                    Side::Black => {
+                        self.black_kind = PlayerKind::Human;
+                        self.record.black = PlayerKind::Human;
+                    }
+                    Side::White => {
+                        self.white_kind = PlayerKind::Human;
+                        self.record.white = PlayerKind::Human;
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    pub fn is_placing_obstacles(&self) -> bool {
+        self.placing_obstacles
+    }
+
+    /// Blocks `pt`, or clears it if blocked, unless a starting disk is there.
+    pub fn toggle_obstacle(&mut self, pt: Point) {
+        if !self.placing_obstacles || self.board.get(pt).is_some() {
+            return;
+        }
+        let blocked = &mut self.record.blocked;
+        match blocked.iter().position(|&b| b == pt) {
+            Some(idx) => {
+                let _ = blocked.remove(idx);
+            }
+            None => blocked.push(pt),
+        }
+        self.board = self.record.initial_board();
+    }
+
+    /// Ends placing obstacles and starts the game.
+    pub fn finish_obstacles(&mut self) {
+        if self.placing_obstacles {
+            self.placing_obstacles = false;
+            self.begin();
         }
     }
 
     fn finish(&mut self) {
         if let Some(p) = mem::replace(&mut self.black_ai_player, None) {
-            p.finish();
+            self.black_summary = p.finish();
         }
         if let Some(p) = mem::replace(&mut self.white_ai_player, None) {
-            p.finish();
+            self.white_summary = p.finish();
         }
     }
 
     pub fn is_waiting_user_input(&self) -> bool {
-        self.board
-            .turn()
-            .map(|side| self.ai_player(side).is_none())
-            .unwrap_or(false)
+        !self.placing_obstacles
+            && self
+                .board
+                .turn()
+                .map(|side| self.ai_player(side).is_none())
+                .unwrap_or(false)
     }
 
     pub fn board(&self) -> &Board {
@@ -53,6 +177,106 @@ impl PlayState {
         }
     }
 
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub fn record_status(&self) -> Option<&str> {
+        self.record_status.as_deref()
+    }
+
+    /// Why the AIs played by the user instead could not start.
+    pub fn ai_errors(&self) -> &[String] {
+        &self.ai_errors
+    }
+
+    /// Writes the game record into `$OTHELLO_RECORDS_DIR`, `records` by
+    /// default.
+    pub fn save_record(&mut self) {
+        self.record_status = Some(match self.write_record() {
+            Ok(path) => format!("saved to {}", path.display()),
+            Err(e) => format!("cannot save the record: {}", e),
+        });
+    }
+
+    pub fn analysis_depth(&self) -> u32 {
+        self.analysis_depth
+    }
+
+    pub fn set_analysis_depth(&mut self, depth: u32) {
+        self.analysis_depth = depth;
+    }
+
+    /// Whether the board marks the disks that can no longer flip.
+    pub fn show_stable(&self) -> bool {
+        self.show_stable
+    }
+
+    pub fn set_show_stable(&mut self, show: bool) {
+        self.show_stable = show;
+    }
+
+    /// Analyses of the moves so far, the move list having one per move once
+    /// the analysis is done.
+    pub fn analyses(&self) -> &[MoveAnalysis] {
+        &self.analyses
+    }
+
+    pub fn is_analyzing(&self) -> bool {
+        self.analysis_rx.is_some()
+    }
+
+    /// Starts analysing the moves in the background, replacing any earlier
+    /// analysis.
+    pub fn start_analysis(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        let record = self.record.clone();
+        let depth = self.analysis_depth;
+        let _ = thread::spawn(move || {
+            for analysis in model::analyze(&record, depth, ANALYSIS_EXACT_EMPTIES) {
+                // The receiver is gone once the game is left.
+                if tx.send(analysis).is_err() {
+                    break;
+                }
+            }
+        });
+        self.analyses.clear();
+        self.analysis_rx = Some(rx);
+    }
+
+    fn receive_analyses(&mut self) {
+        if let Some(rx) = &self.analysis_rx {
+            loop {
+                match rx.try_recv() {
+                    Ok(analysis) => self.analyses.push(analysis),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.analysis_rx = None;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_record(&self) -> io::Result<PathBuf> {
+        let dir = env::var_os("OTHELLO_RECORDS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("records"));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("game-{}.txt", self.record.seed));
+        fs::write(&path, model::annotate(&self.record, &self.analyses))?;
+        Ok(path)
+    }
+
+    /// What the AI playing `side` said about the game once it was over.
+    pub fn summary(&self, side: Side) -> Option<&str> {
+        match side {
+            Side::Black => self.black_summary.as_deref(),
+            Side::White => self.white_summary.as_deref(),
+        }
+    }
+
     fn ai_player(&self, side: Side) -> &Option<AiPlayer> {
         match side {
             Side::Black => &self.black_ai_player,
@@ -65,13 +289,14 @@ impl PlayState {
             Some(turn) => turn,
             None => {
                 self.finish();
+                self.receive_analyses();
                 return;
             }
         };
 
-        let pt = if let Some(ref player) = *self.ai_player(turn) {
+        let mv = if let Some(ref player) = *self.ai_player(turn) {
             match player.listen() {
-                Ok(pt) => pt,
+                Ok(mv) => mv,
                 Err(TryRecvError::Empty) => return,
                 Err(e) => panic!("error: {}", e),
             }
@@ -79,26 +304,44 @@ impl PlayState {
             return;
         };
 
-        if !self.make_move(pt) {
-            panic!("cannot make_move: {:?}", pt);
+        if !self.apply(mv) {
+            panic!("cannot apply the move: {:?}", mv);
         }
     }
 
     pub fn make_move(&mut self, pt: Point) -> bool {
+        self.apply(Move::Place(pt))
+    }
+
+    /// The side whose pass was the last move, until the other side replies.
+    pub fn last_pass(&self) -> Option<Side> {
+        match self.record.moves.last() {
+            Some(Move::Pass) => self.board.turn().map(Side::flip),
+            _ => None,
+        }
+    }
+
+    // Makes `mv` and tells the opponent's AI. A human left without a move
+    // passes at once, while an AI passes by itself.
+    fn apply(&mut self, mv: Move) -> bool {
         let turn = match self.board.turn() {
             Some(turn) => turn,
             None => return false,
         };
 
-        self.board = match self.board.make_move(pt) {
+        self.board = match self.board.apply(mv) {
             None => return false,
             Some(board) => board,
         };
+        self.record.moves.push(mv);
 
         if let Some(ref player) = *self.ai_player(turn.flip()) {
-            player.make_move(turn, pt).unwrap();
+            player.make_move(turn, mv).unwrap();
         }
 
+        if self.board.must_pass() && self.ai_player(turn.flip()).is_none() {
+            let _ = self.apply(Move::Pass);
+        }
         true
     }
 }